@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+
+use crate::utils::{
+    kenney_asset::{KenneySpriteSheetAsset, UnknownSubTexture},
+    pause_system::Pausable,
+};
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            animate_sprites
+                .run_if(resource_equals(Pausable::NotPaused)),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationMode {
+    /// Start over after the last frame, forever
+    Loop,
+    /// Stop on the last frame
+    Once,
+    /// Play forwards then backwards, `cycles` times there
+    /// and back or forever when `None`
+    PingPong { cycles: Option<u32> },
+}
+
+/// Steps the [`TextureAtlas`] of its entity through
+/// `frames` at a fixed rate
+#[derive(Component, Debug, Clone)]
+pub struct SpriteAnimation {
+    frames: Vec<usize>,
+    timer: Timer,
+    mode: AnimationMode,
+    current: usize,
+    backwards: bool,
+    completed_cycles: u32,
+    finished: bool,
+    despawn_on_finish: bool,
+}
+
+impl SpriteAnimation {
+    /// `None` if there are no `frames` to play
+    pub fn new(
+        frames: Vec<usize>,
+        fps: f32,
+        mode: AnimationMode,
+    ) -> Option<SpriteAnimation> {
+        if frames.is_empty() {
+            return None;
+        }
+        Some(SpriteAnimation {
+            frames,
+            timer: Timer::from_seconds(
+                1. / fps,
+                TimerMode::Repeating,
+            ),
+            mode,
+            current: 0,
+            backwards: false,
+            completed_cycles: 0,
+            finished: false,
+            despawn_on_finish: false,
+        })
+    }
+
+    /// Plays the numbered SubTextures starting with
+    /// `prefix`, see [`KenneySpriteSheetAsset::frames`]
+    pub fn from_prefix(
+        sheet: &KenneySpriteSheetAsset,
+        prefix: &str,
+        fps: f32,
+        mode: AnimationMode,
+    ) -> Result<SpriteAnimation, UnknownSubTexture> {
+        SpriteAnimation::new(sheet.frames(prefix)?, fps, mode)
+            .ok_or_else(|| UnknownSubTexture {
+                name: format!("{prefix}*.png"),
+            })
+    }
+
+    /// Despawns the entity once the animation finished
+    pub fn despawn_on_finish(mut self) -> SpriteAnimation {
+        self.despawn_on_finish = true;
+        self
+    }
+
+    /// The atlas index of the frame on show
+    pub fn frame(&self) -> usize {
+        self.frames[self.current]
+    }
+
+    /// A [`TextureAtlas`] on the first frame, to spawn
+    /// alongside the animation
+    pub fn atlas(
+        &self,
+        sheet: &KenneySpriteSheetAsset,
+    ) -> TextureAtlas {
+        TextureAtlas {
+            index: self.frame(),
+            layout: sheet.texture_atlas_layout.clone(),
+        }
+    }
+
+    /// Moves on by one frame, returns true when this step
+    /// finished the animation
+    fn step(&mut self) -> bool {
+        let last = self.frames.len() - 1;
+        match self.mode {
+            AnimationMode::Loop => {
+                self.current = (self.current + 1) % (last + 1);
+                false
+            }
+            AnimationMode::Once => {
+                self.current = (self.current + 1).min(last);
+                self.current == last
+            }
+            AnimationMode::PingPong { cycles } => {
+                if last == 0 {
+                    return cycles.is_some();
+                }
+                if self.backwards {
+                    self.current -= 1;
+                    if self.current == 0 {
+                        self.backwards = false;
+                        self.completed_cycles += 1;
+                        return cycles.is_some_and(|cycles| {
+                            self.completed_cycles >= cycles
+                        });
+                    }
+                } else {
+                    self.current += 1;
+                    if self.current == last {
+                        self.backwards = true;
+                    }
+                }
+                false
+            }
+        }
+    }
+}
+
+fn animate_sprites(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut animations: Query<(
+        Entity,
+        &mut SpriteAnimation,
+        &mut TextureAtlas,
+    )>,
+) {
+    for (entity, mut animation, mut atlas) in &mut animations {
+        if animation.finished {
+            continue;
+        }
+        animation.timer.tick(time.delta());
+        for _ in 0..animation.timer.times_finished_this_tick() {
+            if animation.step() {
+                animation.finished = true;
+                break;
+            }
+        }
+        atlas.index = animation.frame();
+        if animation.finished && animation.despawn_on_finish {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
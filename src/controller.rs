@@ -1,36 +1,44 @@
 use bevy::prelude::*;
+use bevy_hanabi::prelude::EffectSpawner;
 use bevy_xpbd_2d::prelude::*;
-use std::time::Duration;
 
 use crate::{
-    entities::spaceship::{EngineFire, ShipLevels}, utils::{
+    animation::{AnimationMode, SpriteAnimation},
+    effects::{EffectKind, Effects, ThrusterTrail},
+    entities::spaceship::{EngineFire, ShipStats}, utils::{
         asset_loader::ImageAssets, 
         kenney_asset::KenneySpriteSheetAsset, 
         pause_system::Pausable
-    }, CameraFollowsPlayer, GameState, Player
+    }, CameraFollowsPlayer, GameState, InGame, Player
 };   
 
-#[derive(Component)]
-pub struct Laser(Vec2);
-
-#[derive(Resource, Default, Deref, DerefMut)]
-pub struct MovementFactor(pub Vec2);
+/// A laser hit something at `at`
+#[derive(Event)]
+pub struct LaserImpact {
+    pub at: Vec3,
+}
 
 pub struct ControlsPlugin;
 
 impl Plugin for ControlsPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<MovementFactor>().add_systems(
+        app.add_event::<LaserImpact>()
+            .add_systems(
+                PostUpdate,
+                spawn_laser_impacts
+                    .run_if(resource_equals(
+                        Pausable::NotPaused,
+                    ))
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
             Update,
             (
                 player_movement_system
                     .run_if(in_state(GameState::Playing))
                     .before(camera_follows_player_system),
-                weapon_system
+                (engine_fire, thruster_trail)
                     .run_if(in_state(GameState::Playing)),
-                engine_fire
-                    .run_if(in_state(GameState::Playing)),
-                laser_movement,
             )
                 .run_if(resource_equals(
                     Pausable::NotPaused,
@@ -57,9 +65,19 @@ fn engine_fire(
     }
 }
 
+fn thruster_trail(
+    mut query: Query<&mut EffectSpawner, With<ThrusterTrail>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+    let thrusting = keyboard_input.pressed(KeyCode::ArrowUp);
+    for mut spawner in query.iter_mut() {
+        spawner.set_active(thrusting);
+    }
+}
+
 const ROTATION_SPEED: f32 = 1.0;
-const MOVEMENT_SPEED: f32 = 0.01;
-const DECAY_FACTOR: f32 = 0.95;
+const THRUST_TIME: f32 = 1.5; // seconds of thrust from a standstill to top speed
+const COAST_DAMPING: f32 = 3.0; // how quickly the ship drifts to a stop without thrust
 
 fn handle_keyboard_input(keyboard_input: &Res<ButtonInput<KeyCode>>, key: KeyCode) -> f32 {
     if keyboard_input.pressed(key) {
@@ -69,40 +87,37 @@ fn handle_keyboard_input(keyboard_input: &Res<ButtonInput<KeyCode>>, key: KeyCod
     }
 }
 
-fn update_movement_factor(
-    user_facing_direction: Vec2, 
-    keyboard_input: &Res<ButtonInput<KeyCode>>,
-    movement_factor: &mut ResMut<MovementFactor> 
-) {
-    if keyboard_input.pressed(KeyCode::ArrowUp) {
-        movement_factor.0 = (movement_factor.0 + MOVEMENT_SPEED * user_facing_direction).clamp(Vec2::splat(-1.0), Vec2::splat(1.0)); // Move the ship
-    } else {
-        movement_factor.0 = (movement_factor.0 * DECAY_FACTOR).clamp(Vec2::splat(-1.0), Vec2::splat(1.0)); // Decay the movement factor
-    }
-}
+type ShipHandling = (
+    &'static Transform,
+    &'static ShipStats,
+    &'static Mass,
+    &'static mut ExternalForce,
+    &'static mut LinearDamping,
+    &'static mut LinearVelocity,
+    &'static mut AngularVelocity,
+);
 
 fn player_movement_system(
-    time: Res<Time>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut query: Query<(&mut Transform, &ShipLevels), With<Player>>,
-    mut movement_factor: ResMut<MovementFactor>,
+    mut query: Query<ShipHandling, With<Player>>,
 ) {
-    if let Ok((mut transform, ship)) = query.get_single_mut() {
+    if let Ok((transform, ship, mass, mut thrust, mut damping, mut velocity, mut spin)) = query.get_single_mut() {
 
         let rotation_factor = handle_keyboard_input(&keyboard_input, KeyCode::ArrowLeft) - handle_keyboard_input(&keyboard_input, KeyCode::ArrowRight);
 
-        transform.rotate_z(rotation_factor * ship.base_ship_speed().rotation_speed * time.delta_seconds()); // Rotate the ship accross the z axis perpendicularly to the screen on 2d
+        spin.0 = rotation_factor * ship.speed.rotation_speed; // Turn the ship directly, bumps don't leave it spinning
 
         let user_facing_direction = (transform.rotation * Vec3::Y).xy(); // Get the direction the ship is facing
 
-        update_movement_factor(user_facing_direction, &keyboard_input,&mut movement_factor,); // Update the movement factor
-
-        let movement_distance = movement_factor.0 * ship.base_ship_speed().movement_speed * time.delta_seconds(); // Calculate the distance the ship should move
-
-        let translation_delta = movement_distance;
+        if keyboard_input.pressed(KeyCode::ArrowUp) {
+            thrust.set_force(user_facing_direction * mass.0 * ship.speed.movement_speed / THRUST_TIME); // Push the ship forward
+            damping.0 = 0.0;
+        } else {
+            thrust.clear();
+            damping.0 = COAST_DAMPING; // Drift to a stop
+        }
 
-        transform.translation.x += translation_delta.x;
-        transform.translation.y += translation_delta.y;
+        velocity.0 = velocity.0.clamp_length_max(ship.speed.movement_speed); // Thrust and bumps alike stop at top speed
 
     } else {
         // Handle the error case here
@@ -110,7 +125,7 @@ fn player_movement_system(
     }
 }
 
-fn camera_follows_player_system(
+pub fn camera_follows_player_system(
     mut camera_query: Query<(&mut Transform, &CameraFollowsPlayer)>,
     player_query: Query<(&Player, &Transform)>,
 ) {
@@ -125,79 +140,56 @@ fn camera_follows_player_system(
 }
 
 
-fn laser_movement(
-    mut lasers: Query<(&mut Transform, &Laser)>,
-    time: Res<Time>,
-) {
-    for (
-        mut transform,
-        Laser(preexisting_movement_factor),
-    ) in &mut lasers
-    {
-        let laser_facing_direction =
-            transform.rotation * Vec3::Y;
-        let translation_delta = *preexisting_movement_factor
-            + laser_facing_direction.xy()
-                * 1000.
-                * time.delta_seconds();
-        transform.translation.x += translation_delta.x;
-        transform.translation.y += translation_delta.y;
-    }
-}
+const LASER_IMPACT_FPS: f32 = 30.;
+const LASER_IMPACT_SPARK_COLOR: Color = Color::rgb(0.6, 0.8, 1.);
 
-fn weapon_system(
+fn spawn_laser_impacts(
     mut commands: Commands,
-    time: Res<Time>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    query: Query<&Transform, With<Player>>,
-    movement_factor: ResMut<MovementFactor>,
     images: Res<ImageAssets>,
     sheets: Res<Assets<KenneySpriteSheetAsset>>,
-    mut last_shot: Local<Option<Duration>>,
+    mut events: EventReader<LaserImpact>,
+    mut effects: Effects,
 ) {
-    let space_sheet =
-        sheets.get(&images.space_sheet).unwrap();
-
-    let Ok(transform) = query.get_single() else {
-        error!(
-            "Only expected one Player component got {}",
-            query.iter().count()
-        );
+    let Some(space_sheet) = sheets.get(&images.space_sheet)
+    else {
+        warn!("spawn_laser_impacts requires laser sprites to be loaded");
         return;
     };
 
-    if keyboard_input.pressed(KeyCode::Space) {
-        let can_shoot = last_shot.is_none() || {
-            if let Some(shot) = *last_shot {
-                time.elapsed() - shot
-                    > Duration::from_millis(200)
-            } else {
-                false
-            }
+    for LaserImpact { at } in events.read() {
+        effects.play(
+            EffectKind::ImpactSparks,
+            *at,
+            LASER_IMPACT_SPARK_COLOR,
+        );
+        // laserBlue08 to laserBlue11 are the impact bursts
+        let Some(animation) = space_sheet
+            .frames("laserBlue")
+            .ok()
+            .and_then(|frames| frames.get(7..11).map(<[usize]>::to_vec))
+            .and_then(|frames| {
+                SpriteAnimation::new(
+                    frames,
+                    LASER_IMPACT_FPS,
+                    AnimationMode::Once,
+                )
+            })
+        else {
+            warn!("laserBlue08 to laserBlue11 are missing, no impact burst");
+            continue;
         };
-
-        if can_shoot {
-            *last_shot = Some(time.elapsed());
-
-            commands.spawn((
-                SpriteBundle {
-                    transform: *transform,
-                    texture: space_sheet.sheet.clone(),
-                    ..default()
-                },
-                TextureAtlas {
-                    layout: space_sheet
-                        .texture_atlas_layout
-                        .clone(),
-                    index: 105,
-                },
-                Laser(**movement_factor),
-                Collider::triangle(
-                    Vec2::new(0., -27.),
-                    Vec2::new(4.5, 27.),
-                    Vec2::new(-4.5, 27.),
+        let animation = animation.despawn_on_finish();
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_translation(
+                    at.xy().extend(3.),
                 ),
-            ));
-        }
+                texture: space_sheet.sheet.clone(),
+                ..default()
+            },
+            animation.atlas(space_sheet),
+            animation,
+            InGame,
+        ));
     }
 }
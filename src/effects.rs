@@ -0,0 +1,472 @@
+use std::f32::consts::TAU;
+
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
+use bevy_hanabi::prelude::*;
+
+use crate::Player;
+
+/// Particle effects are played through a pool of emitters
+/// per [`EffectKind`], so several can go off in the same
+/// frame. Systems take an [`Effects`] param and call
+/// [`Effects::play`].
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EffectPool>()
+            .add_systems(Startup, spawn_effect_pools)
+            .add_systems(
+                Update,
+                attach_thruster_trails
+                    .run_if(resource_exists::<EffectLibrary>),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EffectKind {
+    /// A meteor or enemy breaking up
+    Explosion,
+    /// The player's ship, bigger and longer lived
+    ShipExplosion,
+    /// A puff at the nose for every shot
+    MuzzleFlash,
+    /// Sparks where a laser hits
+    ImpactSparks,
+    /// Glitter around the ship as ore is scooped up
+    OreSparkle,
+    /// A ring spreading out as the shield takes a hit
+    ShieldRipple,
+    /// Particles rushing in as a ship warps in
+    Warp,
+    /// Exhaust left behind while thrusting. It is not
+    /// pooled, every ship carries its own, see
+    /// [`ThrusterTrail`]
+    ThrusterTrail,
+}
+
+impl EffectKind {
+    pub fn all_kinds() -> [EffectKind; 8] {
+        [
+            EffectKind::Explosion,
+            EffectKind::ShipExplosion,
+            EffectKind::MuzzleFlash,
+            EffectKind::ImpactSparks,
+            EffectKind::OreSparkle,
+            EffectKind::ShieldRipple,
+            EffectKind::Warp,
+            EffectKind::ThrusterTrail,
+        ]
+    }
+    fn name(&self) -> &'static str {
+        match self {
+            EffectKind::Explosion => "explosion",
+            EffectKind::ShipExplosion => "ship_explosion",
+            EffectKind::MuzzleFlash => "muzzle_flash",
+            EffectKind::ImpactSparks => "impact_sparks",
+            EffectKind::OreSparkle => "ore_sparkle",
+            EffectKind::ShieldRipple => "shield_ripple",
+            EffectKind::Warp => "warp",
+            EffectKind::ThrusterTrail => "thruster_trail",
+        }
+    }
+    /// Emitters kept for this kind, the most that can
+    /// play at once
+    fn pool_size(&self) -> usize {
+        match self {
+            EffectKind::Explosion => 16,
+            EffectKind::ShipExplosion => 2,
+            EffectKind::MuzzleFlash => 4,
+            EffectKind::ImpactSparks => 16,
+            EffectKind::OreSparkle => 4,
+            EffectKind::ShieldRipple => 2,
+            EffectKind::Warp => 2,
+            EffectKind::ThrusterTrail => 0,
+        }
+    }
+    fn settings(&self) -> EffectSettings {
+        match self {
+            EffectKind::Explosion => EffectSettings {
+                spawner: Spawner::once(100.0.into(), false),
+                capacity: 512,
+                lifetime: (1.5, 0.),
+                radius: TAU,
+                dimension: ShapeDimension::Surface,
+                speed: 200.,
+                drag: 2.,
+                size: 3.,
+                shrink: false,
+            },
+            EffectKind::ShipExplosion => EffectSettings {
+                spawner: Spawner::once(400.0.into(), false),
+                capacity: 1024,
+                lifetime: (1.5, 1.5),
+                radius: 20.,
+                dimension: ShapeDimension::Volume,
+                speed: 350.,
+                drag: 1.,
+                size: 5.,
+                shrink: false,
+            },
+            EffectKind::MuzzleFlash => EffectSettings {
+                spawner: Spawner::once(12.0.into(), false),
+                capacity: 64,
+                lifetime: (0.08, 0.08),
+                radius: 2.,
+                dimension: ShapeDimension::Volume,
+                speed: 120.,
+                drag: 6.,
+                size: 3.,
+                shrink: true,
+            },
+            EffectKind::ImpactSparks => EffectSettings {
+                spawner: Spawner::once(24.0.into(), false),
+                capacity: 128,
+                lifetime: (0.2, 0.3),
+                radius: 2.,
+                dimension: ShapeDimension::Volume,
+                speed: 250.,
+                drag: 4.,
+                size: 2.,
+                shrink: true,
+            },
+            EffectKind::OreSparkle => EffectSettings {
+                spawner: Spawner::once(30.0.into(), false),
+                capacity: 128,
+                lifetime: (0.4, 0.4),
+                radius: 16.,
+                dimension: ShapeDimension::Volume,
+                speed: 40.,
+                drag: 1.,
+                size: 3.,
+                shrink: true,
+            },
+            EffectKind::ShieldRipple => EffectSettings {
+                spawner: Spawner::once(60.0.into(), false),
+                capacity: 128,
+                lifetime: (0.3, 0.),
+                radius: 50.,
+                dimension: ShapeDimension::Surface,
+                speed: 80.,
+                drag: 3.,
+                size: 3.,
+                shrink: true,
+            },
+            // a negative speed pulls the ring inwards
+            EffectKind::Warp => EffectSettings {
+                spawner: Spawner::once(200.0.into(), false),
+                capacity: 512,
+                lifetime: (0.4, 0.2),
+                radius: 80.,
+                dimension: ShapeDimension::Surface,
+                speed: -160.,
+                drag: 0.,
+                size: 3.,
+                shrink: true,
+            },
+            EffectKind::ThrusterTrail => EffectSettings {
+                spawner: Spawner::rate(80.0.into())
+                    .with_starts_active(false),
+                capacity: 256,
+                lifetime: (0.3, 0.3),
+                radius: 4.,
+                dimension: ShapeDimension::Volume,
+                speed: 30.,
+                drag: 2.,
+                size: 4.,
+                shrink: true,
+            },
+        }
+    }
+}
+
+/// What the effects in the library differ in, they all
+/// spread out from a circle and take their color from
+/// the `spawn_color` property
+struct EffectSettings {
+    spawner: Spawner,
+    capacity: u32,
+    /// Seconds, the least and a random extra on top
+    lifetime: (f32, f32),
+    radius: f32,
+    dimension: ShapeDimension,
+    /// Outwards from the center, negative is inwards
+    speed: f32,
+    drag: f32,
+    size: f32,
+    /// Shrink away over the particle's life instead of
+    /// keeping `size`
+    shrink: bool,
+}
+
+/// Named handles of every effect, built at startup
+#[derive(Resource)]
+pub struct EffectLibrary(HashMap<EffectKind, Handle<EffectAsset>>);
+
+impl EffectLibrary {
+    pub fn get(&self, kind: EffectKind) -> Handle<EffectAsset> {
+        self.0[&kind].clone()
+    }
+}
+
+/// A ship's exhaust, running while it thrusts
+#[derive(Component)]
+pub struct ThrusterTrail;
+
+/// The emitters of each kind and which one plays next
+#[derive(Resource, Default)]
+pub struct EffectPool {
+    emitters: HashMap<EffectKind, Vec<Entity>>,
+    next: HashMap<EffectKind, usize>,
+}
+
+impl EffectPool {
+    /// Takes emitters round-robin. Once the whole pool is
+    /// playing, the next effect cuts the oldest one short.
+    fn take(&mut self, kind: EffectKind) -> Option<Entity> {
+        let emitters = self
+            .emitters
+            .get(&kind)
+            .filter(|emitters| !emitters.is_empty())?;
+        let next = self.next.entry(kind).or_default();
+        let entity = *emitters.get(*next % emitters.len())?;
+        *next = (*next + 1) % emitters.len();
+        Some(entity)
+    }
+}
+
+#[derive(Component)]
+struct PooledEffect;
+
+#[derive(SystemParam)]
+pub struct Effects<'w, 's> {
+    pool: ResMut<'w, EffectPool>,
+    emitters: Query<
+        'w,
+        's,
+        (
+            &'static mut EffectProperties,
+            Option<&'static mut EffectSpawner>,
+            &'static mut Transform,
+        ),
+        With<PooledEffect>,
+    >,
+}
+
+impl Effects<'_, '_> {
+    /// Sets off an effect of `kind` at `position`, its
+    /// particles tinted `color`
+    pub fn play(
+        &mut self,
+        kind: EffectKind,
+        position: Vec3,
+        color: Color,
+    ) {
+        let Some(entity) = self.pool.take(kind) else {
+            warn!("no {kind:?} emitters to play");
+            return;
+        };
+        let Ok((mut properties, spawner, mut transform)) =
+            self.emitters.get_mut(entity)
+        else {
+            return;
+        };
+        // Note: EffectSpawner is added during PostUpdate
+        // of the first frame, skip the effect if so
+        let Some(mut spawner) = spawner else {
+            warn!("effect not ready yet");
+            return;
+        };
+        transform.translation = position;
+        properties.set(
+            "spawn_color",
+            color.as_linear_rgba_u32().into(),
+        );
+        spawner.reset();
+    }
+}
+
+fn spawn_effect_pools(
+    mut commands: Commands,
+    mut assets: ResMut<Assets<EffectAsset>>,
+    mut pool: ResMut<EffectPool>,
+) {
+    let mut library = HashMap::new();
+    for kind in EffectKind::all_kinds() {
+        let handle = assets.add(build_effect(kind));
+        let emitters = (0..kind.pool_size())
+            .map(|index| {
+                commands
+                    .spawn((
+                        ParticleEffectBundle::new(handle.clone()),
+                        EffectProperties::default(),
+                        PooledEffect,
+                        Name::new(format!(
+                            "effect:{}:{index}",
+                            kind.name()
+                        )),
+                    ))
+                    .id()
+            })
+            .collect();
+        pool.emitters.insert(kind, emitters);
+        library.insert(kind, handle);
+    }
+    commands.insert_resource(EffectLibrary(library));
+}
+
+fn build_effect(kind: EffectKind) -> EffectAsset {
+    let settings = kind.settings();
+
+    let writer = ExprWriter::new();
+
+    let age = writer.lit(0.).expr();
+    let init_age =
+        SetAttributeModifier::new(Attribute::AGE, age);
+    let (least, extra) = settings.lifetime;
+    let lifetime = (writer.lit(least)
+        + writer.lit(extra) * writer.rand(ScalarType::Float))
+    .expr();
+    let init_lifetime = SetAttributeModifier::new(
+        Attribute::LIFETIME,
+        lifetime,
+    );
+
+    let drag = writer.lit(settings.drag).expr();
+    let update_drag = LinearDragModifier::new(drag);
+
+    let color = writer.prop("spawn_color").expr();
+    let init_color =
+        SetAttributeModifier::new(Attribute::COLOR, color);
+
+    let init_pos = SetPositionCircleModifier {
+        center: writer.lit(Vec3::ZERO).expr(),
+        axis: writer.lit(Vec3::Z).expr(),
+        radius: writer.lit(settings.radius).expr(),
+        dimension: settings.dimension,
+    };
+
+    let init_vel = SetVelocityCircleModifier {
+        center: writer.lit(Vec3::ZERO).expr(),
+        axis: writer.lit(Vec3::Z).expr(),
+        speed: (writer.lit(settings.speed)
+            * writer.rand(ScalarType::Float))
+        .expr(),
+    };
+
+    let effect = EffectAsset::new(
+        settings.capacity,
+        settings.spawner,
+        writer.finish(),
+    )
+    .with_name(kind.name())
+    .with_property("spawn_color", 0xFFFFFFFFu32.into())
+    .init(init_pos)
+    .init(init_vel)
+    .init(init_age)
+    .init(init_lifetime)
+    .init(init_color)
+    .update(update_drag);
+
+    let size = Vec2::splat(settings.size);
+    if settings.shrink {
+        let mut gradient = Gradient::new();
+        gradient.add_key(0., size);
+        gradient.add_key(1., Vec2::ZERO);
+        effect.render(SizeOverLifetimeModifier {
+            gradient,
+            screen_space_size: true,
+        })
+    } else {
+        effect.render(SetSizeModifier {
+            size: size.into(),
+            screen_space_size: true,
+        })
+    }
+}
+
+/// Gives every new player ship its own exhaust trail
+fn attach_thruster_trails(
+    mut commands: Commands,
+    library: Res<EffectLibrary>,
+    ships: Query<Entity, Added<Player>>,
+) {
+    for ship in &ships {
+        let trail = commands
+            .spawn((
+                ParticleEffectBundle {
+                    effect: ParticleEffect::new(
+                        library.get(EffectKind::ThrusterTrail),
+                    ),
+                    transform: Transform::from_xyz(0., -40., -1.),
+                    ..default()
+                },
+                EffectProperties::default().with_properties([(
+                    "spawn_color".to_string(),
+                    THRUSTER_TRAIL_COLOR.as_linear_rgba_u32().into(),
+                )]),
+                ThrusterTrail,
+                Name::new("effect:thruster_trail"),
+            ))
+            .id();
+        commands.entity(ship).add_child(trail);
+    }
+}
+
+const THRUSTER_TRAIL_COLOR: Color = Color::rgb(1., 0.7, 0.3);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(kind: EffectKind, emitters: &[Entity]) -> EffectPool {
+        let mut pool = EffectPool::default();
+        pool.emitters.insert(kind, emitters.to_vec());
+        pool
+    }
+
+    #[test]
+    fn takes_emitters_in_turn_and_wraps_around() {
+        let emitters = [
+            Entity::from_raw(1),
+            Entity::from_raw(2),
+            Entity::from_raw(3),
+        ];
+        let mut pool = pool(EffectKind::Explosion, &emitters);
+        let taken: Vec<Entity> = (0..5)
+            .map(|_| pool.take(EffectKind::Explosion).unwrap())
+            .collect();
+        assert_eq!(
+            taken,
+            [
+                emitters[0],
+                emitters[1],
+                emitters[2],
+                emitters[0],
+                emitters[1],
+            ]
+        );
+    }
+
+    #[test]
+    fn kinds_take_turns_separately() {
+        let mut pool = pool(EffectKind::Explosion, &[Entity::from_raw(1)]);
+        pool.emitters.insert(
+            EffectKind::Warp,
+            vec![Entity::from_raw(2), Entity::from_raw(3)],
+        );
+        assert_eq!(pool.take(EffectKind::Warp), Some(Entity::from_raw(2)));
+        assert_eq!(
+            pool.take(EffectKind::Explosion),
+            Some(Entity::from_raw(1))
+        );
+        assert_eq!(pool.take(EffectKind::Warp), Some(Entity::from_raw(3)));
+    }
+
+    #[test]
+    fn an_empty_pool_has_nothing_to_take() {
+        let mut pool = pool(EffectKind::Explosion, &[]);
+        assert_eq!(pool.take(EffectKind::Explosion), None);
+        assert_eq!(pool.take(EffectKind::Warp), None);
+    }
+}
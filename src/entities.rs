@@ -1,4 +1,15 @@
 pub mod collisions;
 pub mod spaceship;
 pub mod meteor;
-pub mod lives;
\ No newline at end of file
+pub mod lives;
+pub mod ore;
+pub mod score;
+pub mod waves;
+pub mod station;
+pub mod upgrades;
+pub mod ship_builder;
+pub mod weapon;
+pub mod powerups;
+pub mod enemies;
+pub mod boss;
+pub mod health;
@@ -0,0 +1,664 @@
+use std::{
+    f32::consts::{FRAC_PI_2, FRAC_PI_4, TAU},
+    time::Duration,
+};
+
+use bevy::{prelude::*, sprite::Anchor};
+use bevy_xpbd_2d::prelude::*;
+
+use crate::{
+    entities::{
+        enemies::{spawn_enemy, spawn_enemy_laser, EnemyKind},
+        health::{announce_deaths, Damage, Died, Health},
+        ore::{Ore, OreBundle, OreType},
+        powerups::{PowerUpBundle, PowerUpKind},
+    },
+    utils::{
+        asset_loader::{FontAssets, ImageAssets},
+        kenney_asset::KenneySpriteSheetAsset,
+        pause_system::Pausable,
+    },
+    GameState, InGame, Player,
+};
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BossDefeated>()
+            .add_systems(
+                PostUpdate,
+                (break_boss_parts, drop_boss_reward)
+                    .chain()
+                    .after(announce_deaths)
+                    .run_if(resource_equals(Pausable::NotPaused))
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (
+                    move_boss,
+                    update_boss_phase,
+                    aim_boss_guns,
+                    boss_attacks,
+                    render_boss_health_bar,
+                )
+                    .chain()
+                    .run_if(resource_equals(
+                        Pausable::NotPaused,
+                    ))
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// The boss gets harder as its health drops
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BossPhase {
+    One,
+    Two,
+    Three,
+}
+
+impl BossPhase {
+    /// The phase with `fraction` of the boss's health left
+    fn from_health(fraction: f32) -> BossPhase {
+        if fraction > 0.6 {
+            BossPhase::One
+        } else if fraction > 0.25 {
+            BossPhase::Two
+        } else {
+            BossPhase::Three
+        }
+    }
+    fn attack_cooldown(&self) -> Duration {
+        match self {
+            BossPhase::One => Duration::from_millis(3000),
+            BossPhase::Two => Duration::from_millis(2400),
+            BossPhase::Three => Duration::from_millis(1800),
+        }
+    }
+    fn telegraph_time(&self) -> Duration {
+        match self {
+            BossPhase::One => Duration::from_millis(900),
+            BossPhase::Two => Duration::from_millis(700),
+            BossPhase::Three => Duration::from_millis(500),
+        }
+    }
+    /// Attacks the boss takes turns with
+    fn patterns(&self) -> &'static [AttackPattern] {
+        match self {
+            BossPhase::One => &[AttackPattern::Volley],
+            BossPhase::Two => {
+                &[AttackPattern::Volley, AttackPattern::Ring]
+            }
+            BossPhase::Three => &[
+                AttackPattern::Ring,
+                AttackPattern::Volley,
+                AttackPattern::Ring,
+            ],
+        }
+    }
+    fn ring_lasers(&self) -> u32 {
+        match self {
+            BossPhase::Three => 16,
+            _ => 12,
+        }
+    }
+    fn bar_color(&self) -> &'static str {
+        match self {
+            BossPhase::One => "green",
+            BossPhase::Two => "yellow",
+            BossPhase::Three => "red",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttackPattern {
+    /// Every turret fires a short fan at the player
+    Volley,
+    /// The hull fires a ring of lasers all around
+    Ring,
+}
+
+/// An attack winding up, the parts firing it blink red
+/// until `timer` finishes
+#[derive(Debug)]
+pub struct Telegraph {
+    pub pattern: AttackPattern,
+    pub timer: Timer,
+}
+
+/// The root of a boss, its [`BossPart`]s are children
+#[derive(Component, Debug)]
+pub struct Boss {
+    pub phase: BossPhase,
+    pub health: f32,
+    pub max_health: f32,
+    pub points: u32,
+    pub attack: Timer,
+    pub attacks: usize,
+    pub telegraph: Option<Telegraph>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BossPartKind {
+    /// Destroying the hull defeats the boss
+    Hull,
+    Turret,
+}
+
+/// A piece of a boss that can be shot off
+#[derive(Component, Debug)]
+pub struct BossPart {
+    pub kind: BossPartKind,
+}
+
+/// A turret's barrel, it tracks the player
+#[derive(Component)]
+struct BossGun;
+
+#[derive(Event)]
+pub struct BossDefeated {
+    pub defeated_at: Transform,
+    pub points: u32,
+}
+
+const HULL_SPRITE: &str = "ufoRed.png";
+const HULL_SCALE: f32 = 2.2;
+const HULL_HEALTH: f32 = 30.;
+const TURRET_SPRITE: &str = "turretBase_big.png";
+const GUN_SPRITE: &str = "gun09.png";
+const TURRET_HEALTH: f32 = 8.;
+const TURRET_RADIUS: f32 = 95.;
+const BOSS_POINTS: u32 = 2000;
+/// Flying into the boss is fatal for any hull
+const BOSS_RAM_DAMAGE: f32 = 100.;
+
+/// Spawns a mothership, a hull ringed by four turrets.
+/// `strength` scales its health and reward for later
+/// encounters.
+pub fn spawn_boss(
+    commands: &mut Commands,
+    space_sheet: &KenneySpriteSheetAsset,
+    transform: Transform,
+    strength: f32,
+) -> Entity {
+    let hull_health = HULL_HEALTH * strength;
+    let turret_health = TURRET_HEALTH * strength;
+    let max_health = hull_health + 4. * turret_health;
+    let phase = BossPhase::One;
+    commands
+        .spawn((
+            SpatialBundle::from_transform(transform),
+            Boss {
+                phase,
+                health: max_health,
+                max_health,
+                points: (BOSS_POINTS as f32 * strength) as u32,
+                attack: Timer::new(
+                    phase.attack_cooldown(),
+                    TimerMode::Repeating,
+                ),
+                attacks: 0,
+                telegraph: None,
+            },
+            InGame,
+            Name::new("Boss"),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                SpriteBundle {
+                    transform: Transform::from_scale(
+                        Vec3::splat(HULL_SCALE),
+                    ),
+                    texture: space_sheet.sheet.clone(),
+                    ..default()
+                },
+                space_sheet
+                    .atlas(HULL_SPRITE)
+                    .expect("boss hull sprite to exist"),
+                BossPart {
+                    kind: BossPartKind::Hull,
+                },
+                Health::new(hull_health),
+                Damage(BOSS_RAM_DAMAGE),
+                Collider::circle(44.),
+            ));
+            for index in 0..4 {
+                let angle = FRAC_PI_4 + index as f32 * FRAC_PI_2;
+                let offset = Vec2::from_angle(angle) * TURRET_RADIUS;
+                parent
+                    .spawn((
+                        SpriteBundle {
+                            transform: Transform::from_translation(
+                                offset.extend(1.),
+                            ),
+                            texture: space_sheet.sheet.clone(),
+                            ..default()
+                        },
+                        space_sheet
+                            .atlas(TURRET_SPRITE)
+                            .expect("turret sprite to exist"),
+                        BossPart {
+                            kind: BossPartKind::Turret,
+                        },
+                        Health::new(turret_health),
+                        Damage(BOSS_RAM_DAMAGE),
+                        Collider::circle(20.),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            SpriteBundle {
+                                transform: Transform::from_xyz(
+                                    0., 0., 1.,
+                                ),
+                                texture: space_sheet.sheet.clone(),
+                                sprite: Sprite {
+                                    anchor: Anchor::BottomCenter,
+                                    ..default()
+                                },
+                                ..default()
+                            },
+                            space_sheet
+                                .atlas(GUN_SPRITE)
+                                .expect("gun sprite to exist"),
+                            BossGun,
+                        ));
+                    });
+            }
+        })
+        .id()
+}
+
+const BOSS_SPEED: f32 = 60.;
+/// The boss closes in to this distance from the player
+const BOSS_HOLD_DISTANCE: f32 = 380.;
+
+fn move_boss(
+    time: Res<Time>,
+    player: Query<&Transform, With<Player>>,
+    mut bosses: Query<&mut Transform, (With<Boss>, Without<Player>)>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    for mut transform in &mut bosses {
+        let to_player =
+            player.translation.xy() - transform.translation.xy();
+        if to_player.length() <= BOSS_HOLD_DISTANCE {
+            continue;
+        }
+        transform.translation += (to_player.normalize()
+            * BOSS_SPEED
+            * time.delta_seconds())
+        .extend(0.);
+    }
+}
+
+/// Sums the parts' health and moves to the next phase
+/// once it drops below a threshold
+fn update_boss_phase(
+    mut commands: Commands,
+    images: Res<ImageAssets>,
+    sheets: Res<Assets<KenneySpriteSheetAsset>>,
+    mut bosses: Query<(&mut Boss, &Transform, &Children)>,
+    parts: Query<&Health, With<BossPart>>,
+) {
+    for (mut boss, transform, children) in &mut bosses {
+        let health: f32 = parts
+            .iter_many(children)
+            .map(|health| health.current.max(0.))
+            .sum();
+        boss.health = health;
+        let phase =
+            BossPhase::from_health(health / boss.max_health);
+        if phase == boss.phase {
+            continue;
+        }
+        info!("Boss entering {phase:?}");
+        boss.phase = phase;
+        boss.attack = Timer::new(
+            phase.attack_cooldown(),
+            TimerMode::Repeating,
+        );
+        // the last phase calls in escorts
+        if phase == BossPhase::Three {
+            let Some(space_sheet) =
+                sheets.get(&images.space_sheet)
+            else {
+                continue;
+            };
+            for side in [-1., 1.] {
+                spawn_enemy(
+                    &mut commands,
+                    EnemyKind::Kamikaze,
+                    Transform::from_translation(
+                        transform.translation
+                            + Vec3::new(side * 160., 0., 0.),
+                    ),
+                    space_sheet,
+                );
+            }
+        }
+    }
+}
+
+type BossGunNotPlayer = (With<BossGun>, Without<Player>);
+
+fn aim_boss_guns(
+    player: Query<&Transform, With<Player>>,
+    mut guns: Query<
+        (&GlobalTransform, &mut Transform),
+        BossGunNotPlayer,
+    >,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    for (global_transform, mut transform) in &mut guns {
+        let to_player = player.translation.xy()
+            - global_transform.translation().xy();
+        // the boss never turns, so local is world rotation
+        transform.rotation = Quat::from_rotation_z(
+            to_player.y.atan2(to_player.x) - FRAC_PI_2,
+        );
+    }
+}
+
+/// Blinks per second while an attack winds up
+const TELEGRAPH_BLINK: f32 = 8.;
+const VOLLEY_LASERS: u32 = 3;
+const VOLLEY_SPREAD: f32 = 0.15;
+
+/// Winds attacks up, then fires them
+fn boss_attacks(
+    mut commands: Commands,
+    time: Res<Time>,
+    images: Res<ImageAssets>,
+    sheets: Res<Assets<KenneySpriteSheetAsset>>,
+    player: Query<&Transform, With<Player>>,
+    mut bosses: Query<(&mut Boss, &Children)>,
+    mut parts: Query<(&BossPart, &GlobalTransform, &mut Sprite)>,
+) {
+    let Some(space_sheet) = sheets.get(&images.space_sheet)
+    else {
+        return;
+    };
+    let target = player
+        .get_single()
+        .map(|player| player.translation.xy())
+        .ok();
+    for (mut boss, children) in &mut bosses {
+        let boss = boss.as_mut();
+        let Some(telegraph) = boss.telegraph.as_mut() else {
+            if boss.attack.tick(time.delta()).just_finished() {
+                let patterns = boss.phase.patterns();
+                boss.telegraph = Some(Telegraph {
+                    pattern: patterns[boss.attacks % patterns.len()],
+                    timer: Timer::new(
+                        boss.phase.telegraph_time(),
+                        TimerMode::Once,
+                    ),
+                });
+                boss.attacks += 1;
+            }
+            continue;
+        };
+
+        let firing = match telegraph.pattern {
+            AttackPattern::Volley => BossPartKind::Turret,
+            AttackPattern::Ring => BossPartKind::Hull,
+        };
+        let finished = telegraph.timer.tick(time.delta()).finished();
+        let blink = (telegraph.timer.elapsed_secs()
+            * TELEGRAPH_BLINK
+            * TAU)
+            .sin()
+            .abs();
+        let mut iter = parts.iter_many_mut(children);
+        while let Some((part, transform, mut sprite)) =
+            iter.fetch_next()
+        {
+            if part.kind != firing {
+                continue;
+            }
+            if !finished {
+                sprite.color =
+                    Color::rgb(1., 1. - blink, 1. - blink);
+                continue;
+            }
+            sprite.color = Color::WHITE;
+            let position = transform.translation();
+            match telegraph.pattern {
+                AttackPattern::Volley => {
+                    let Some(target) = target else {
+                        continue;
+                    };
+                    let aim = target - position.xy();
+                    for index in 0..VOLLEY_LASERS {
+                        let offset = index as f32
+                            - (VOLLEY_LASERS - 1) as f32 / 2.;
+                        spawn_enemy_laser(
+                            &mut commands,
+                            space_sheet,
+                            position,
+                            Vec2::from_angle(offset * VOLLEY_SPREAD)
+                                .rotate(aim),
+                        );
+                    }
+                }
+                AttackPattern::Ring => {
+                    let lasers = boss.phase.ring_lasers();
+                    // each ring is turned a little from the last
+                    let twist = boss.attacks as f32 * 0.2;
+                    for index in 0..lasers {
+                        let angle =
+                            twist + index as f32 * TAU / lasers as f32;
+                        spawn_enemy_laser(
+                            &mut commands,
+                            space_sheet,
+                            position,
+                            Vec2::from_angle(angle),
+                        );
+                    }
+                }
+            }
+        }
+        if finished {
+            boss.telegraph = None;
+        }
+    }
+}
+
+#[derive(Component)]
+struct BossHealthBar;
+
+/// A piece of the health bar, `left`, `mid` or `right`,
+/// recolored per phase
+#[derive(Component)]
+struct BossBarPiece(&'static str);
+
+const BOSS_BAR_WIDTH: f32 = 400.;
+
+fn render_boss_health_bar(
+    mut commands: Commands,
+    images: Res<ImageAssets>,
+    fonts: Res<FontAssets>,
+    sheets: Res<Assets<KenneySpriteSheetAsset>>,
+    bosses: Query<&Boss>,
+    bars: Query<Entity, With<BossHealthBar>>,
+    mut pieces: Query<(&BossBarPiece, &mut TextureAtlas, &mut Style)>,
+) {
+    let Some(ui_sheet) = sheets.get(&images.ui_sheet) else {
+        return;
+    };
+    let boss = bosses.iter().next();
+    let bar = bars.get_single().ok();
+    let Some(boss) = boss else {
+        if let Some(bar) = bar {
+            commands.entity(bar).despawn_recursive();
+        }
+        return;
+    };
+    let color = boss.phase.bar_color();
+    let piece_atlas = |piece: &str| {
+        ui_sheet
+            .atlas(&format!("barHorizontal_{color}_{piece}.png"))
+            .expect("bar sprite to exist")
+    };
+
+    if bar.is_none() {
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        width: Val::Percent(100.),
+                        top: Val::Px(20.),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(4.),
+                        ..default()
+                    },
+                    ..default()
+                },
+                BossHealthBar,
+                InGame,
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    "Mothership",
+                    TextStyle {
+                        font: fonts.alfa_slab_one_regular.clone(),
+                        font_size: 22.0,
+                        color: Color::WHITE,
+                    },
+                ));
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Px(BOSS_BAR_WIDTH + 12.),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        for (piece, width) in
+                            [("left", 6.), ("mid", 0.), ("right", 6.)]
+                        {
+                            parent.spawn((
+                                ImageBundle {
+                                    style: Style {
+                                        width: Val::Px(width),
+                                        height: Val::Px(26.),
+                                        ..default()
+                                    },
+                                    image: ui_sheet
+                                        .sheet
+                                        .clone()
+                                        .into(),
+                                    ..default()
+                                },
+                                piece_atlas(piece),
+                                BossBarPiece(piece),
+                            ));
+                        }
+                    });
+            });
+        return;
+    }
+
+    let fraction = (boss.health / boss.max_health).clamp(0., 1.);
+    for (BossBarPiece(piece), mut atlas, mut style) in &mut pieces {
+        let wanted = piece_atlas(piece);
+        if atlas.index != wanted.index {
+            *atlas = wanted;
+        }
+        if *piece == "mid" {
+            style.width = Val::Px(BOSS_BAR_WIDTH * fraction);
+        }
+    }
+}
+
+/// Shot off turrets fall away, losing the hull takes
+/// the whole boss down
+fn break_boss_parts(
+    mut commands: Commands,
+    mut events: EventReader<Died>,
+    mut boss_defeated: EventWriter<BossDefeated>,
+    parts: Query<(&BossPart, &Parent)>,
+    bosses: Query<(&Boss, &Transform)>,
+) {
+    for Died { entity } in events.read() {
+        let Ok((part, parent)) = parts.get(*entity) else {
+            continue;
+        };
+        match part.kind {
+            BossPartKind::Turret => {
+                commands.entity(*entity).despawn_recursive();
+            }
+            BossPartKind::Hull => {
+                commands.entity(parent.get()).despawn_recursive();
+                if let Ok((boss, transform)) = bosses.get(parent.get())
+                {
+                    boss_defeated.send(BossDefeated {
+                        defeated_at: *transform,
+                        points: boss.points,
+                    });
+                }
+            }
+        }
+    }
+}
+
+const REWARD_GOLD: u32 = 15;
+const REWARD_SPRITE: &str = "star_gold.png";
+const REWARD_RING: f32 = 70.;
+
+/// A boss leaves a gold star worth a hold full of gold
+/// and one of every power-up
+fn drop_boss_reward(
+    mut commands: Commands,
+    images: Res<ImageAssets>,
+    sheets: Res<Assets<KenneySpriteSheetAsset>>,
+    mut events: EventReader<BossDefeated>,
+) {
+    let Some(space_sheet) = sheets.get(&images.space_sheet)
+    else {
+        warn!("drop_boss_reward requires reward sprites to be loaded");
+        return;
+    };
+
+    for BossDefeated { defeated_at, .. } in events.read() {
+        let center = defeated_at.translation.xy();
+        commands
+            .spawn(OreBundle::new(
+                Ore {
+                    ore_type: OreType::Gold,
+                    amount: REWARD_GOLD,
+                },
+                Transform::from_translation(center.extend(1.))
+                    .with_scale(Vec3::splat(1.5)),
+                space_sheet,
+            ))
+            .insert(
+                space_sheet
+                    .atlas(REWARD_SPRITE)
+                    .expect("reward sprite to exist"),
+            );
+        let kinds = PowerUpKind::all_kinds();
+        for (index, kind) in kinds.iter().enumerate() {
+            let angle = index as f32 * TAU / kinds.len() as f32;
+            commands.spawn(PowerUpBundle::new(
+                *kind,
+                Transform::from_translation(
+                    (center + Vec2::from_angle(angle) * REWARD_RING)
+                        .extend(1.),
+                ),
+                space_sheet,
+            ));
+        }
+    }
+}
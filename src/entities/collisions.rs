@@ -3,93 +3,299 @@ use bevy_xpbd_2d::prelude::*;
 
 use crate::{
     entities::{ 
-        meteor::{
-            Meteor, MeteorDestroyed, MeteorType
+        boss::BossPart,
+        enemies::{Enemy, EnemyLaser},
+        health::{deal_damage, Damage, Damaged, Health, Invulnerable},
+        meteor::{Meteor, RAM_REFERENCE_SPEED},
+        ore::{Ore, OreCollected},
+        powerups::{
+            PowerUp, PowerUpCollected, PowerUpEffects, ShieldHit,
         },
-        spaceship::ShipDestroyed,
+        weapon::Projectile,
     },
-    controller::Laser,
+    controller::LaserImpact,
     Player
 };   
 
-pub fn laser_meteor_collision(
+/// What a meteor brings to a ramming
+type RammingMeteor = (
+    Entity,
+    &'static mut Health,
+    &'static Damage,
+    &'static LinearVelocity,
+);
+
+/// The ship's side of a collision, its shield may soak
+/// the hit
+type HitShip = (
+    Entity,
+    &'static Transform,
+    &'static CollidingEntities,
+    &'static mut Health,
+    Has<Invulnerable>,
+    Option<&'static mut PowerUpEffects>,
+);
+
+/// Player shots hurt anything with [`Health`] they touch,
+/// meteors, enemies and boss parts alike
+pub fn laser_collision(
     mut commands: Commands,
-    mut meteor_destroyed: EventWriter<MeteorDestroyed>,
-    lasers: Query<Entity, With<Laser>>,
-    meteors: Query<
-        (
-            Entity,
-            &CollidingEntities,
-            &MeteorType,
-            &Transform,
-        ),
-        With<Meteor>,
+    mut damaged: EventWriter<Damaged>,
+    mut laser_impact: EventWriter<LaserImpact>,
+    mut lasers: Query<
+        (Entity, &Transform, &mut Projectile),
+        Without<EnemyLaser>,
+    >,
+    mut targets: Query<
+        (Entity, &CollidingEntities, &mut Health),
+        Without<Player>,
     >,
 ) {
-    for (
-        entity_meteor,
-        colliding_entities,
-        meteor_type,
-        transform,
-    ) in &meteors
+    for (entity_target, colliding_entities, mut health) in
+        &mut targets
     {
-        if !colliding_entities.is_empty() {
-            for entity_laser in &lasers {
-                if colliding_entities
-                    .contains(&entity_laser)
-                {
-                    commands
-                        .entity(entity_laser)
-                        .despawn_recursive();
-                    commands
-                        .entity(entity_meteor)
-                        .despawn_recursive();
-
-                    meteor_destroyed.send(
-                        MeteorDestroyed {
-                            destroyed_at: *transform,
-                            destroyed_type: *meteor_type,
-                        },
-                    );
-                }
+        if colliding_entities.is_empty() {
+            continue;
+        }
+        for (entity_laser, laser_transform, mut laser) in
+            &mut lasers
+        {
+            if !colliding_entities.contains(&entity_laser)
+                || laser.hit.contains(&entity_target)
+            {
+                continue;
             }
+            // several shots can land in the same frame
+            if !deal_damage(
+                &mut damaged,
+                entity_target,
+                &mut health,
+                laser.damage,
+            ) {
+                continue;
+            }
+            // piercing shots carry on to the next target
+            if laser.pierce == 0 {
+                commands
+                    .entity(entity_laser)
+                    .despawn_recursive();
+            } else {
+                laser.pierce -= 1;
+                laser.hit.push(entity_target);
+            }
+            laser_impact.send(LaserImpact {
+                at: laser_transform.translation,
+            });
         }
     }
 }
 
+/// Ramming hurts both the ship and the meteor, harder the
+/// bigger the meteor and the faster they closed in
 pub fn ship_meteor_collision(
-    mut commands: Commands,
-    mut ship_destroyed: EventWriter<ShipDestroyed>,
-    meteors: Query<Entity, With<Meteor>>,
-    player_ship: Query<
+    mut damaged: EventWriter<Damaged>,
+    mut shield_hit: EventWriter<ShieldHit>,
+    mut meteors: Query<
+        RammingMeteor,
+        (With<Meteor>, Without<Player>),
+    >,
+    mut player_ship: Query<(HitShip, &LinearVelocity), With<Player>>,
+) {
+    for (
         (
-            Entity,
-            &CollidingEntities,
-            &Transform,
+            entity_player,
+            transform,
+            colliding_entities,
+            mut health,
+            invulnerable,
+            mut effects,
         ),
-        With<Player>,
+        ship_velocity,
+    ) in &mut player_ship
+    {
+        if colliding_entities.is_empty() || invulnerable {
+            continue;
+        }
+        for (
+            entity_meteor,
+            mut meteor_health,
+            Damage(damage),
+            meteor_velocity,
+        ) in &mut meteors
+        {
+            if !colliding_entities.contains(&entity_meteor) {
+                continue;
+            }
+            // a shield takes the hit and breaks the
+            // meteor instead
+            if effects
+                .as_mut()
+                .is_some_and(|effects| effects.absorb_hit())
+            {
+                shield_hit.send(ShieldHit {
+                    at: transform.translation,
+                });
+                let remaining = meteor_health.current;
+                deal_damage(
+                    &mut damaged,
+                    entity_meteor,
+                    &mut meteor_health,
+                    remaining,
+                );
+                continue;
+            }
+            // the physics step may have bounced them apart
+            // already, how fast they part still tells how
+            // hard they hit
+            let closing_speed =
+                (ship_velocity.0 - meteor_velocity.0).length();
+            let amount = damage
+                * (closing_speed / RAM_REFERENCE_SPEED)
+                    .clamp(0.5, 2.);
+            deal_damage(
+                &mut damaged,
+                entity_meteor,
+                &mut meteor_health,
+                amount,
+            );
+            deal_damage(
+                &mut damaged,
+                entity_player,
+                &mut health,
+                amount,
+            );
+            // the hit grace covers anything else touching
+            break;
+        }
+    }
+}
+
+pub fn ship_ore_collision(
+    mut commands: Commands,
+    mut ore_collected: EventWriter<OreCollected>,
+    ores: Query<(Entity, &Ore, &Transform)>,
+    player_ship: Query<&CollidingEntities, With<Player>>,
+) {
+    for colliding_entities in &player_ship {
+        if colliding_entities.is_empty() {
+            continue;
+        }
+        for (entity_ore, ore, transform) in &ores {
+            if colliding_entities.contains(&entity_ore) {
+                commands
+                    .entity(entity_ore)
+                    .despawn_recursive();
+
+                ore_collected.send(OreCollected {
+                    ore: *ore,
+                    at: transform.translation,
+                });
+            }
+        }
+    }
+}
+
+pub fn ship_power_up_collision(
+    mut commands: Commands,
+    mut power_up_collected: EventWriter<PowerUpCollected>,
+    power_ups: Query<(Entity, &PowerUp)>,
+    player_ship: Query<&CollidingEntities, With<Player>>,
+) {
+    for colliding_entities in &player_ship {
+        if colliding_entities.is_empty() {
+            continue;
+        }
+        for (entity_power_up, PowerUp(kind)) in &power_ups {
+            if colliding_entities.contains(&entity_power_up) {
+                commands
+                    .entity(entity_power_up)
+                    .despawn_recursive();
+
+                power_up_collected
+                    .send(PowerUpCollected { kind: *kind });
+            }
+        }
+    }
+}
+
+type EnemyNotPlayer = (With<Enemy>, Without<Player>);
+
+/// Enemy lasers, enemies ramming the ship and the boss
+/// hull all go through the ship's [`Health`]
+pub fn enemy_ship_collision(
+    mut commands: Commands,
+    mut damaged: EventWriter<Damaged>,
+    mut shield_hit: EventWriter<ShieldHit>,
+    enemy_lasers: Query<(Entity, &Projectile), With<EnemyLaser>>,
+    mut enemies: Query<
+        (Entity, &mut Health, &Damage),
+        EnemyNotPlayer,
     >,
+    boss_parts: Query<&Damage, With<BossPart>>,
+    mut player_ship: Query<HitShip, With<Player>>,
 ) {
     for (
         entity_player,
-        colliding_entities,
         transform,
-    ) in &player_ship
+        colliding_entities,
+        mut health,
+        invulnerable,
+        mut effects,
+    ) in &mut player_ship
     {
-        if !colliding_entities.is_empty() {
-            for entity_meteor in &meteors {
-                if colliding_entities
-                    .contains(&entity_meteor)
-                {
-                    commands
-                        .entity(entity_player)
-                        .despawn_recursive();
-
-                    ship_destroyed.send(ShipDestroyed {
-                        destroyed_at: *transform,
-                    });
+        if colliding_entities.is_empty() {
+            continue;
+        }
+        let mut hits = Vec::new();
+        for (entity_laser, laser) in &enemy_lasers {
+            if colliding_entities.contains(&entity_laser) {
+                commands
+                    .entity(entity_laser)
+                    .despawn_recursive();
+                hits.push(laser.damage);
+            }
+        }
+        // rammers break up on the hull
+        for (entity_enemy, mut enemy_health, Damage(damage)) in
+            &mut enemies
+        {
+            if colliding_entities.contains(&entity_enemy) {
+                let remaining = enemy_health.current;
+                if deal_damage(
+                    &mut damaged,
+                    entity_enemy,
+                    &mut enemy_health,
+                    remaining,
+                ) {
+                    hits.push(*damage);
                 }
             }
         }
+        // flying into a boss hurts, the boss is unharmed
+        hits.extend(
+            colliding_entities
+                .iter()
+                .filter_map(|entity| boss_parts.get(*entity).ok())
+                .map(|Damage(damage)| *damage),
+        );
+        if invulnerable {
+            continue;
+        }
+        // only the hardest hit counts, the hit grace
+        // covers the rest
+        let Some(amount) = hits.into_iter().reduce(f32::max)
+        else {
+            continue;
+        };
+        if effects
+            .as_mut()
+            .is_some_and(|effects| effects.absorb_hit())
+        {
+            shield_hit.send(ShieldHit {
+                at: transform.translation,
+            });
+            continue;
+        }
+        deal_damage(&mut damaged, entity_player, &mut health, amount);
     }
-}
\ No newline at end of file
+}
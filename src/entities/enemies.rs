@@ -0,0 +1,396 @@
+use std::{f32::consts::FRAC_PI_2, time::Duration};
+
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    entities::{
+        health::{announce_deaths, Damage, Died, Health},
+        weapon::{
+            Projectile, ProjectileBodyBundle, ProjectileLifetime,
+        },
+    },
+    utils::{
+        asset_loader::ImageAssets,
+        kenney_asset::KenneySpriteSheetAsset,
+        pause_system::Pausable,
+    },
+    GameState, InGame, Player,
+};
+
+pub struct EnemyPlugin;
+
+impl Plugin for EnemyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EnemyDestroyed>()
+            .add_systems(
+                Update,
+                (steer_enemies, fire_enemy_guns)
+                    .chain()
+                    .run_if(resource_equals(Pausable::NotPaused))
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                PostUpdate,
+                destroy_enemies
+                    .after(announce_deaths)
+                    .run_if(resource_equals(Pausable::NotPaused))
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(
+    Component, Clone, Copy, Debug, PartialEq, Eq, Deserialize,
+)]
+pub enum EnemyKind {
+    /// Closes in and shoots from up close
+    Fighter,
+    /// Keeps its distance and slides from side to side
+    Strafer,
+    /// A UFO circling the player
+    Orbiter,
+    /// Dives straight into the player, unarmed
+    Kamikaze,
+}
+
+impl EnemyKind {
+    pub fn all_kinds() -> [EnemyKind; 4] {
+        [
+            EnemyKind::Fighter,
+            EnemyKind::Strafer,
+            EnemyKind::Orbiter,
+            EnemyKind::Kamikaze,
+        ]
+    }
+    pub fn sprite_name(&self) -> &'static str {
+        match self {
+            EnemyKind::Fighter => "enemyBlack1.png",
+            EnemyKind::Strafer => "enemyBlue2.png",
+            EnemyKind::Orbiter => "ufoGreen.png",
+            EnemyKind::Kamikaze => "enemyRed4.png",
+        }
+    }
+    pub fn health(&self) -> f32 {
+        match self {
+            EnemyKind::Fighter => 3.,
+            EnemyKind::Strafer => 2.,
+            EnemyKind::Orbiter => 4.,
+            EnemyKind::Kamikaze => 1.,
+        }
+    }
+    /// Top speed in pixels per second
+    pub fn speed(&self) -> f32 {
+        match self {
+            EnemyKind::Fighter => 180.,
+            EnemyKind::Strafer => 220.,
+            EnemyKind::Orbiter => 160.,
+            EnemyKind::Kamikaze => 320.,
+        }
+    }
+    pub fn points(&self) -> u32 {
+        match self {
+            EnemyKind::Fighter => 150,
+            EnemyKind::Strafer => 200,
+            EnemyKind::Orbiter => 250,
+            EnemyKind::Kamikaze => 100,
+        }
+    }
+    /// Damage dealt by ramming the player
+    pub fn ram_damage(&self) -> f32 {
+        match self {
+            EnemyKind::Kamikaze => 2.,
+            _ => 1.,
+        }
+    }
+    /// Time between shots, `None` for unarmed enemies
+    pub fn fire_cooldown(&self) -> Option<Duration> {
+        match self {
+            EnemyKind::Fighter => Some(Duration::from_millis(1500)),
+            EnemyKind::Strafer => Some(Duration::from_millis(1200)),
+            EnemyKind::Orbiter => Some(Duration::from_millis(2000)),
+            EnemyKind::Kamikaze => None,
+        }
+    }
+    pub fn behaviour(&self) -> Behaviour {
+        match self {
+            EnemyKind::Fighter => Behaviour::Chase,
+            EnemyKind::Strafer => Behaviour::Strafe {
+                side: 1.,
+                switch: Timer::new(
+                    STRAFE_SWITCH,
+                    TimerMode::Repeating,
+                ),
+            },
+            EnemyKind::Orbiter => Behaviour::Orbit,
+            EnemyKind::Kamikaze => Behaviour::Kamikaze,
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Enemy {
+    pub kind: EnemyKind,
+}
+
+/// How an enemy moves relative to the player
+#[derive(Component, Debug, Clone)]
+pub enum Behaviour {
+    Chase,
+    Strafe { side: f32, switch: Timer },
+    Orbit,
+    Kamikaze,
+}
+
+#[derive(Component)]
+pub struct EnemyGun(pub Timer);
+
+/// A projectile fired by an enemy, it only hurts the
+/// player
+#[derive(Component)]
+pub struct EnemyLaser;
+
+#[derive(Event)]
+pub struct EnemyDestroyed {
+    pub destroyed_at: Transform,
+    pub kind: EnemyKind,
+}
+
+#[derive(Bundle)]
+pub struct EnemyBundle {
+    enemy: Enemy,
+    behaviour: Behaviour,
+    health: Health,
+    damage: Damage,
+    collider: Collider,
+    sprite_bundle: SpriteBundle,
+    texture_atlas: TextureAtlas,
+    in_game: InGame,
+}
+
+const ENEMY_SCALE: f32 = 0.8;
+
+impl EnemyBundle {
+    pub fn new(
+        kind: EnemyKind,
+        transform: Transform,
+        space_sheet: &KenneySpriteSheetAsset,
+    ) -> EnemyBundle {
+        EnemyBundle {
+            enemy: Enemy { kind },
+            behaviour: kind.behaviour(),
+            health: Health::new(kind.health()),
+            damage: Damage(kind.ram_damage()),
+            collider: Collider::circle(40.),
+            sprite_bundle: SpriteBundle {
+                transform: transform
+                    .with_scale(Vec3::splat(ENEMY_SCALE)),
+                texture: space_sheet.sheet.clone(),
+                ..default()
+            },
+            texture_atlas: space_sheet
+                .atlas(kind.sprite_name())
+                .expect("enemy sprite to exist"),
+            in_game: InGame,
+        }
+    }
+}
+
+/// Spawns an enemy of `kind`, armed if the kind carries
+/// a gun
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    kind: EnemyKind,
+    transform: Transform,
+    space_sheet: &KenneySpriteSheetAsset,
+) -> Entity {
+    let mut enemy = commands.spawn(EnemyBundle::new(
+        kind,
+        transform,
+        space_sheet,
+    ));
+    if let Some(cooldown) = kind.fire_cooldown() {
+        enemy.insert(EnemyGun(Timer::new(
+            cooldown,
+            TimerMode::Repeating,
+        )));
+    }
+    enemy.id()
+}
+
+/// Chasers stop this far from the player to shoot
+const CHASE_DISTANCE: f32 = 220.;
+const STRAFE_DISTANCE: f32 = 320.;
+const STRAFE_SWITCH: Duration = Duration::from_millis(1800);
+const ORBIT_RADIUS: f32 = 260.;
+const KAMIKAZE_TURN_RATE: f32 = 1.5; // radians per second
+
+/// Enemy sprites point down, this turns their nose
+/// towards `direction`
+fn facing(direction: Vec2) -> Quat {
+    Quat::from_rotation_z(
+        direction.y.atan2(direction.x) + FRAC_PI_2,
+    )
+}
+
+fn steer_enemies(
+    time: Res<Time>,
+    player: Query<&Transform, With<Player>>,
+    mut enemies: Query<
+        (&mut Transform, &mut Behaviour, &Enemy),
+        Without<Player>,
+    >,
+) {
+    // enemies hold still while the player respawns
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let target = player.translation.xy();
+    let delta = time.delta_seconds();
+    for (mut transform, mut behaviour, enemy) in &mut enemies {
+        let position = transform.translation.xy();
+        let to_player = target - position;
+        let distance = to_player.length();
+        let towards = to_player.normalize_or_zero();
+        let speed = enemy.kind.speed();
+        // keeps circling enemies at `radius`
+        let hold = |radius: f32| {
+            towards * (distance - radius).clamp(-speed, speed)
+        };
+        let velocity = match behaviour.as_mut() {
+            Behaviour::Chase => {
+                if distance > CHASE_DISTANCE {
+                    towards * speed
+                } else {
+                    Vec2::ZERO
+                }
+            }
+            Behaviour::Strafe { side, switch } => {
+                if switch.tick(time.delta()).just_finished() {
+                    *side = -*side;
+                }
+                (hold(STRAFE_DISTANCE) + towards.perp() * *side * speed)
+                    .clamp_length_max(speed)
+            }
+            Behaviour::Orbit => (hold(ORBIT_RADIUS)
+                + towards.perp() * speed)
+                .clamp_length_max(speed),
+            Behaviour::Kamikaze => {
+                let nose = (transform.rotation * Vec3::NEG_Y).xy();
+                let max_turn = KAMIKAZE_TURN_RATE * delta;
+                transform.rotate_z(
+                    nose.angle_between(towards)
+                        .clamp(-max_turn, max_turn),
+                );
+                (transform.rotation * Vec3::NEG_Y).xy() * speed
+            }
+        };
+        if !matches!(*behaviour, Behaviour::Kamikaze) {
+            transform.rotation = facing(towards);
+        }
+        transform.translation += (velocity * delta).extend(0.);
+    }
+}
+
+const ENEMY_LASER_SPRITE: &str = "laserRed01.png";
+const ENEMY_LASER_SPEED: f32 = 500.;
+const ENEMY_LASER_RANGE: f32 = 700.;
+const ENEMY_GUN_RANGE: f32 = 600.;
+
+/// Spawns a red laser at `position` flying along
+/// `direction`, bosses fire these too
+pub fn spawn_enemy_laser(
+    commands: &mut Commands,
+    space_sheet: &KenneySpriteSheetAsset,
+    position: Vec3,
+    direction: Vec2,
+) {
+    let size = space_sheet
+        .size_of(ENEMY_LASER_SPRITE)
+        .expect("enemy laser sprite to exist");
+    // lasers fly along their own y axis
+    let rotation = Quat::from_rotation_z(
+        direction.y.atan2(direction.x) - FRAC_PI_2,
+    );
+    commands.spawn((
+        SpriteBundle {
+            transform: Transform::from_translation(position)
+                .with_rotation(rotation),
+            texture: space_sheet.sheet.clone(),
+            ..default()
+        },
+        space_sheet
+            .atlas(ENEMY_LASER_SPRITE)
+            .expect("enemy laser sprite to exist"),
+        Projectile {
+            inherited: Vec2::ZERO,
+            speed: ENEMY_LASER_SPEED,
+            damage: 1.,
+            pierce: 0,
+            hit: Vec::new(),
+        },
+        ProjectileLifetime {
+            timer: Timer::from_seconds(2., TimerMode::Once),
+            origin: position.xy(),
+            range: ENEMY_LASER_RANGE,
+        },
+        EnemyLaser,
+        ProjectileBodyBundle::new(
+            direction.normalize_or_zero() * ENEMY_LASER_SPEED,
+        ),
+        InGame,
+        Collider::capsule(size.y - size.x, size.x / 2.),
+    ));
+}
+
+fn fire_enemy_guns(
+    mut commands: Commands,
+    time: Res<Time>,
+    images: Res<ImageAssets>,
+    sheets: Res<Assets<KenneySpriteSheetAsset>>,
+    player: Query<&Transform, With<Player>>,
+    mut guns: Query<(&Transform, &mut EnemyGun), Without<Player>>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let Some(space_sheet) = sheets.get(&images.space_sheet)
+    else {
+        return;
+    };
+    for (transform, mut gun) in &mut guns {
+        if !gun.0.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let to_player =
+            player.translation.xy() - transform.translation.xy();
+        if to_player.length() > ENEMY_GUN_RANGE {
+            continue;
+        }
+        spawn_enemy_laser(
+            &mut commands,
+            space_sheet,
+            transform.translation,
+            to_player,
+        );
+    }
+}
+
+fn destroy_enemies(
+    mut commands: Commands,
+    mut events: EventReader<Died>,
+    mut enemy_destroyed: EventWriter<EnemyDestroyed>,
+    enemies: Query<(&Enemy, &Transform)>,
+) {
+    for Died { entity } in events.read() {
+        let Ok((enemy, transform)) = enemies.get(*entity) else {
+            continue;
+        };
+        commands.entity(*entity).despawn_recursive();
+        enemy_destroyed.send(EnemyDestroyed {
+            destroyed_at: *transform,
+            kind: enemy.kind,
+        });
+    }
+}
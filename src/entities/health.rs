@@ -0,0 +1,296 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, utils::HashSet};
+
+use crate::{utils::pause_system::Pausable, GameState};
+
+/// Turns [`Damaged`] into [`Died`] once health runs out.
+/// Whoever owns the dying entity reacts to [`Died`] and
+/// despawns it.
+pub struct HealthPlugin;
+
+impl Plugin for HealthPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Damaged>()
+            .add_event::<Died>()
+            .add_systems(
+                Update,
+                (
+                    tick_invulnerability,
+                    blink_invulnerable,
+                    fade_hit_flash,
+                )
+                    .chain()
+                    .run_if(resource_equals(Pausable::NotPaused))
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                PostUpdate,
+                (announce_deaths, grant_hit_grace, flash_on_hit)
+                    .run_if(resource_equals(Pausable::NotPaused))
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Health {
+        Health { current: max, max }
+    }
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.
+    }
+    pub fn fraction(&self) -> f32 {
+        (self.current / self.max).clamp(0., 1.)
+    }
+}
+
+/// How hard this hits whatever it runs into
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Damage(pub f32);
+
+/// Ignores hits until the timer finishes
+#[derive(Component)]
+pub struct Invulnerable(pub Timer);
+
+/// Makes an entity [`Invulnerable`] for a while after
+/// each hit, so one long touch is not a hit per frame
+#[derive(Component, Clone, Copy, Debug)]
+pub struct HitGrace(pub Duration);
+
+#[derive(Event, Debug)]
+pub struct Damaged {
+    pub entity: Entity,
+    pub amount: f32,
+}
+
+#[derive(Event, Debug)]
+pub struct Died {
+    pub entity: Entity,
+}
+
+/// Takes `amount` off `health` and reports it. Returns
+/// false, and does nothing, if `entity` is already dead.
+pub fn deal_damage(
+    damaged: &mut EventWriter<Damaged>,
+    entity: Entity,
+    health: &mut Health,
+    amount: f32,
+) -> bool {
+    if health.is_dead() {
+        return false;
+    }
+    health.current -= amount;
+    damaged.send(Damaged { entity, amount });
+    true
+}
+
+pub fn announce_deaths(
+    mut events: EventReader<Damaged>,
+    mut died: EventWriter<Died>,
+    healths: Query<&Health>,
+) {
+    // several hits can finish an entity in one frame
+    let mut dead = HashSet::new();
+    for Damaged { entity, .. } in events.read() {
+        let Ok(health) = healths.get(*entity) else {
+            continue;
+        };
+        if health.is_dead() && dead.insert(*entity) {
+            died.send(Died { entity: *entity });
+        }
+    }
+}
+
+fn grant_hit_grace(
+    mut commands: Commands,
+    mut events: EventReader<Damaged>,
+    graces: Query<(&HitGrace, &Health)>,
+) {
+    for Damaged { entity, .. } in events.read() {
+        let Ok((HitGrace(duration), health)) = graces.get(*entity)
+        else {
+            continue;
+        };
+        if health.is_dead() {
+            continue;
+        }
+        commands.entity(*entity).try_insert(Invulnerable(
+            Timer::new(*duration, TimerMode::Once),
+        ));
+    }
+}
+
+const HIT_FLASH_DURATION: Duration = Duration::from_millis(150);
+
+/// Tints a sprite red after a hit, harder hits flash
+/// deeper. `base` is the color it goes back to.
+#[derive(Component)]
+struct HitFlash {
+    timer: Timer,
+    strength: f32,
+    base: Color,
+}
+
+fn flash_on_hit(
+    mut commands: Commands,
+    mut events: EventReader<Damaged>,
+    healths: Query<(&Health, &Sprite, Option<&HitFlash>)>,
+) {
+    for Damaged { entity, amount } in events.read() {
+        let Ok((health, sprite, flash)) = healths.get(*entity)
+        else {
+            continue;
+        };
+        if health.is_dead() {
+            continue;
+        }
+        // a hit during a flash keeps the color from before
+        // the first one
+        let base =
+            flash.map_or(sprite.color, |flash| flash.base);
+        commands.entity(*entity).try_insert(HitFlash {
+            timer: Timer::new(HIT_FLASH_DURATION, TimerMode::Once),
+            strength: (amount / health.max).clamp(0.3, 1.),
+            base,
+        });
+    }
+}
+
+fn fade_hit_flash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut flashes: Query<(Entity, &mut HitFlash, &mut Sprite)>,
+) {
+    for (entity, mut flash, mut sprite) in &mut flashes {
+        if flash.timer.tick(time.delta()).finished() {
+            sprite.color = flash.base;
+            commands.entity(entity).remove::<HitFlash>();
+            continue;
+        }
+        let tint = flash.strength * flash.timer.fraction_remaining();
+        let base = flash.base;
+        sprite.color = Color::rgba(
+            base.r(),
+            base.g() * (1. - tint),
+            base.b() * (1. - tint),
+            base.a(),
+        );
+    }
+}
+
+fn tick_invulnerability(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut invulnerable: Query<(Entity, &mut Invulnerable)>,
+) {
+    for (entity, mut invulnerable) in &mut invulnerable {
+        if invulnerable.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Invulnerable>();
+        }
+    }
+}
+
+/// Blinks per second while invulnerable
+const INVULNERABLE_BLINK: f32 = 8.;
+
+fn blink_invulnerable(
+    mut blinking: Query<(&Invulnerable, &mut Visibility)>,
+    mut removed: RemovedComponents<Invulnerable>,
+    mut visibilities: Query<&mut Visibility, Without<Invulnerable>>,
+) {
+    for (invulnerable, mut visibility) in &mut blinking {
+        let phase = invulnerable.0.elapsed_secs() * INVULNERABLE_BLINK;
+        *visibility = if phase % 2. < 1. {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    for entity in removed.read() {
+        if let Ok(mut visibility) = visibilities.get_mut(entity) {
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Events<Damaged>>();
+        world.init_resource::<Events<Died>>();
+        world
+    }
+
+    fn hit(world: &mut World, entity: Entity, amount: f32) -> bool {
+        world.run_system_once(
+            move |mut damaged: EventWriter<Damaged>,
+                  mut healths: Query<&mut Health>| {
+                let mut health = healths.get_mut(entity).unwrap();
+                deal_damage(&mut damaged, entity, &mut health, amount)
+            },
+        )
+    }
+
+    /// Runs [`announce_deaths`] over this round's hits
+    fn deaths(world: &mut World) -> Vec<Entity> {
+        world.run_system_once(announce_deaths);
+        world.resource_mut::<Events<Damaged>>().clear();
+        world
+            .resource_mut::<Events<Died>>()
+            .drain()
+            .map(|died| died.entity)
+            .collect()
+    }
+
+    #[test]
+    fn damage_is_taken_off_and_reported() {
+        let mut world = world();
+        let entity = world.spawn(Health::new(3.)).id();
+        assert!(hit(&mut world, entity, 1.));
+
+        assert_eq!(world.get::<Health>(entity).unwrap().current, 2.);
+        let damaged: Vec<(Entity, f32)> = world
+            .resource_mut::<Events<Damaged>>()
+            .drain()
+            .map(|damaged| (damaged.entity, damaged.amount))
+            .collect();
+        assert_eq!(damaged, [(entity, 1.)]);
+    }
+
+    #[test]
+    fn the_dead_take_no_more_damage() {
+        let mut world = world();
+        let entity = world.spawn(Health::new(1.)).id();
+        assert!(hit(&mut world, entity, 2.));
+        assert!(!hit(&mut world, entity, 2.));
+        assert_eq!(world.get::<Health>(entity).unwrap().current, -1.);
+        assert_eq!(world.resource::<Events<Damaged>>().len(), 1);
+    }
+
+    #[test]
+    fn deaths_are_announced_once() {
+        let mut world = world();
+        let dying = world.spawn(Health::new(2.)).id();
+        let hurt = world.spawn(Health::new(5.)).id();
+        // two hits finish it in the same frame
+        hit(&mut world, dying, 1.);
+        hit(&mut world, dying, 1.);
+        hit(&mut world, hurt, 1.);
+        assert_eq!(deaths(&mut world), [dying]);
+
+        hit(&mut world, hurt, 1.);
+        assert!(deaths(&mut world).is_empty());
+    }
+}
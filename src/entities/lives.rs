@@ -2,7 +2,7 @@ use bevy::prelude::*;
 
 use crate::{
     utils::{asset_loader::ImageAssets, kenney_asset::KenneySpriteSheetAsset},
-    entities::spaceship::ShipLevels,
+    entities::spaceship::{ShipColor, ShipLevels},
     GameState,
     Player,
 };
@@ -45,11 +45,11 @@ fn lives(
             Some(new_lives) => {
                 lives.0 = new_lives;
                 if lives.0 == 0 {
-                    next_state.set(GameState::Menu);
+                    next_state.set(GameState::GameOver);
                 }
             }
             None => {
-                next_state.set(GameState::Menu);
+                next_state.set(GameState::GameOver);
             }
         }
     }
@@ -89,10 +89,14 @@ fn render_lives(
     lives: Res<Lives>,
     life_container: Query<Entity, With<LifeContainer>>,
     sheets: Res<Assets<KenneySpriteSheetAsset>>,
-    player_query: Query<&ShipLevels, With<Player>>,
+    player_query: Query<
+        (Ref<ShipLevels>, &ShipColor),
+        With<Player>,
+    >,
     life_sprite_query: Query<(Entity, &LifeIndex)>,
 ) {
-    let Ok(ship_type) = player_query.get_single() else {
+    let Ok((ship_type, ship_color)) = player_query.get_single()
+    else {
         if player_query.iter().count() > 1 {
             error_once!(
             "Only expected one PlayerShipType component. got {}",
@@ -107,9 +111,18 @@ fn render_lives(
 
     let container_id = life_container.single();
 
+    // the icon follows the ship, redraw them all after an
+    // upgrade
+    let redraw = ship_type.is_changed();
+    if redraw {
+        for (entity, _) in &life_sprite_query {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
     for index in 0..lives.0 {
         // IF the live is currently shown
-        if life_sprite_query.iter().any(
+        if !redraw && life_sprite_query.iter().any(
             |(_entity, life_index)| index == life_index.0,
         ) {
             // life already exists on screen, and should,
@@ -125,12 +138,11 @@ fn render_lives(
                             .into(),
                         ..default()
                     },
-                    TextureAtlas {
-                        index: ship_type.life_atlas_index(),
-                        layout: space_sheet
-                            .texture_atlas_layout
-                            .clone(),
-                    },
+                    space_sheet
+                        .atlas(
+                            &ship_type.life_sprite_name(*ship_color),
+                        )
+                        .expect("life sprite to exist"),
                     LifeIndex(index),
                 ))
                 .id();
@@ -141,6 +153,9 @@ fn render_lives(
     }
 
     // remove unused lives
+    if redraw {
+        return;
+    }
     for (entity, index) in &life_sprite_query {
         if index.0 >= lives.0 {
             commands.entity(entity).despawn_recursive();
@@ -2,335 +2,464 @@ use std::f32::consts::TAU;
 
 use bevy::prelude::*;
 use bevy_xpbd_2d::prelude::*;
-use bevy_hanabi::prelude::*;
 
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    movement::{LinearMovement, MovementWrapper, Rotate2D}, 
-    utils::{asset_loader::ImageAssets, kenney_asset::KenneySpriteSheetAsset, pause_system::Pausable},
+    effects::{EffectKind, Effects},
+    entities::{
+        health::{announce_deaths, Damage, Died, Health},
+        waves::CurrentWave,
+    },
+    movement::MovementWrapper,
+    utils::{asset_loader::SpriteSheets, kenney_asset::KenneySpriteSheetAsset, level_asset::CurrentLevel, pause_system::Pausable},
     GameState,
+    InGame,
 };
 
 
 pub struct MeteorPlugin;
 impl Plugin for MeteorPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, register_meteor_effect)
-            .add_systems(
-                PostUpdate,
-                sandbox_meteor_destroyed_event_handler
-                    .run_if(resource_equals(
-                        Pausable::NotPaused,
-                    ))
-                    .run_if(in_state(GameState::Playing)),
-            )
-            .add_event::<MeteorDestroyed>();
+        app.add_systems(
+            PostUpdate,
+            break_meteors
+                .after(announce_deaths)
+                .run_if(resource_equals(
+                    Pausable::NotPaused,
+                ))
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            PostUpdate,
+            sandbox_meteor_destroyed_event_handler
+                .run_if(resource_equals(
+                    Pausable::NotPaused,
+                ))
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_event::<MeteorDestroyed>();
     }
 }
 
-fn register_meteor_effect(
-    mut commands: Commands,
-    mut effects: ResMut<Assets<EffectAsset>>,
-) {
-    let spawner = Spawner::once(100.0.into(), false);
-
-    let writer = ExprWriter::new();
-
-    let age = writer.lit(0.).expr();
-    let init_age =
-        SetAttributeModifier::new(Attribute::AGE, age);
-    let lifetime = writer.lit(1.5).expr();
-    let init_lifetime = SetAttributeModifier::new(
-        Attribute::LIFETIME,
-        lifetime,
-    );
-
-    let drag = writer.lit(2.).expr();
-    let update_drag = LinearDragModifier::new(drag);
-
-    let color = writer.prop("spawn_color").expr();
-    let init_color =
-        SetAttributeModifier::new(Attribute::COLOR, color);
-
-    let init_pos = SetPositionCircleModifier {
-        center: writer.lit(Vec3::Y).expr(),
-        axis: writer.lit(Vec3::Z).expr(),
-        radius: writer.lit(TAU).expr(),
-        dimension: ShapeDimension::Surface,
-    };
-
-    let init_vel = SetVelocityCircleModifier {
-        center: writer.lit(Vec3::ZERO).expr(),
-        axis: writer.lit(Vec3::Z).expr(),
-        speed: (writer.lit(200.)
-            * writer.rand(ScalarType::Float))
-        .expr(),
-    };
-
-    let effect = effects.add(
-        EffectAsset::new(32768, spawner, writer.finish())
-            .with_name("explosion")
-            .with_property(
-                "spawn_color",
-                0xFFFFFFFFu32.into(),
-            )
-            .init(init_pos)
-            .init(init_vel)
-            .init(init_age)
-            .init(init_lifetime)
-            .init(init_color)
-            .update(update_drag)
-            .render(SetSizeModifier {
-                size: Vec2::splat(3.).into(),
-                screen_space_size: true,
-            }),
-    );
-
-    commands
-        .spawn((
-            ParticleEffectBundle::new(effect)
-                .with_spawner(spawner),
-            EffectProperties::default(),
-        ))
-        .insert(Name::new("effect:meteor_explosion"));
-}
-
-
 #[derive(Bundle)]
 pub struct MeteorBundle {
     meteor_type: MeteorType,
+    material: MeteorMaterial,
     meteor: Meteor,
+    health: Health,
+    damage: Damage,
     collider: Collider,
     sprite_bundle: SpriteBundle,
     texture_atlas: TextureAtlas,
-    linear_movement: LinearMovement,
-    spin: Rotate2D,
+    body: MeteorBodyBundle,
     wrapping: MovementWrapper,
+    in_game: InGame,
+}
+/// Meteors drift and spin freely and bounce off each
+/// other and the ship. Their mass comes from the
+/// collider's area, so big meteors shove small ones
+/// aside.
+#[derive(Bundle)]
+pub struct MeteorBodyBundle {
+    rigid_body: RigidBody,
+    velocity: LinearVelocity,
+    spin: AngularVelocity,
+    density: ColliderDensity,
+    restitution: Restitution,
+    // slow drifters would otherwise freeze in place
+    sleeping: SleepingDisabled,
 }
-#[derive(Component, Clone, Copy)]
+
+impl MeteorBodyBundle {
+    pub fn new(velocity: Vec2, spin: f32) -> MeteorBodyBundle {
+        MeteorBodyBundle {
+            rigid_body: RigidBody::Dynamic,
+            velocity: LinearVelocity(velocity),
+            spin: AngularVelocity(spin),
+            density: ColliderDensity(METEOR_DENSITY),
+            restitution: Restitution::new(METEOR_RESTITUTION),
+            sleeping: SleepingDisabled,
+        }
+    }
+}
+
+const METEOR_DENSITY: f32 = 1.;
+const METEOR_RESTITUTION: f32 = 0.8;
+
+#[derive(Component, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum MeteorType {
     Big,
     Medium,
     Small,
 }
+impl MeteorType {
+    /// Smaller meteors are harder to hit and worth more
+    pub fn points(&self) -> u32 {
+        match self {
+            MeteorType::Big => 20,
+            MeteorType::Medium => 50,
+            MeteorType::Small => 100,
+        }
+    }
+    /// Laser hits it takes to break
+    pub fn health(&self) -> f32 {
+        match self {
+            MeteorType::Big => 3.,
+            MeteorType::Medium => 2.,
+            MeteorType::Small => 1.,
+        }
+    }
+    /// Damage when rammed at [`RAM_REFERENCE_SPEED`]
+    pub fn collision_damage(&self) -> f32 {
+        match self {
+            MeteorType::Big => 3.,
+            MeteorType::Medium => 2.,
+            MeteorType::Small => 1.,
+        }
+    }
+}
+
+/// Relative speed at which ramming a meteor deals its
+/// [`MeteorType::collision_damage`] unscaled
+pub const RAM_REFERENCE_SPEED: f32 = 200.;
+
+/// What a meteor is made of, which decides the sprite
+/// and the ore it drops
+#[derive(
+    Component, Clone, Copy, Debug, Default, PartialEq, Eq,
+    Serialize, Deserialize,
+)]
+pub enum MeteorMaterial {
+    Brown,
+    #[default]
+    Grey,
+}
+
+impl MeteorMaterial {
+    pub fn random() -> MeteorMaterial {
+        if rand::random::<bool>() {
+            MeteorMaterial::Brown
+        } else {
+            MeteorMaterial::Grey
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct Meteor;
 
-const METEOR_BASE_SPEED_BIG: f32 = 1.;
-const METEOR_BASE_SPEED_MEDIUM: f32 = 1.2;
-const METEOR_BASE_SPEED_SMALL: f32 = 1.4;
+/// How each size of meteor looks, how big it is and how
+/// it drifts, set per level
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MeteorSizes {
+    pub big: MeteorSize,
+    pub medium: MeteorSize,
+    pub small: MeteorSize,
+}
 
-impl MeteorBundle {
-    pub fn big(
-        transform: Transform,
-        space_sheet: &KenneySpriteSheetAsset,
-    ) -> MeteorBundle {
-        let mut rng = rand::thread_rng();
-        let x = rng.gen::<f32>() * METEOR_BASE_SPEED_BIG;
-        let y = rng.gen::<f32>() * METEOR_BASE_SPEED_BIG;
-        let rotation = rng.gen::<f32>() * TAU;
+impl MeteorSizes {
+    pub fn get(&self, meteor_type: MeteorType) -> &MeteorSize {
+        match meteor_type {
+            MeteorType::Big => &self.big,
+            MeteorType::Medium => &self.medium,
+            MeteorType::Small => &self.small,
+        }
+    }
+}
 
-        MeteorBundle {
-            meteor_type: MeteorType::Big,
-            meteor: Meteor,
-            collider: Collider::circle(42.),
-            sprite_bundle: SpriteBundle {
-                transform,
-                texture: space_sheet.sheet.clone(),
-                ..default()
+impl Default for MeteorSizes {
+    fn default() -> Self {
+        MeteorSizes {
+            big: MeteorSize {
+                brown_sprite: "meteorBrown_big1.png".to_string(),
+                grey_sprite: "meteorGrey_big1.png".to_string(),
+                radius: 42.,
+                max_speed: 60.,
+                spin: 1.2,
             },
-            texture_atlas: TextureAtlas {
-                index: 163,
-                layout: space_sheet
-                    .texture_atlas_layout
-                    .clone(),
+            medium: MeteorSize {
+                brown_sprite: "meteorBrown_med1.png".to_string(),
+                grey_sprite: "meteorGrey_med1.png".to_string(),
+                radius: 21.,
+                max_speed: 72.,
+                spin: 1.6,
             },
-            linear_movement: LinearMovement {
-                movement_factor: Vec2::new(
-                    x as f32, y as f32,
-                ),
-                movement_direction: Quat::from_rotation_z(
-                    rotation,
-                ),
+            small: MeteorSize {
+                brown_sprite: "meteorBrown_small1.png".to_string(),
+                grey_sprite: "meteorGrey_small1.png".to_string(),
+                radius: 14.,
+                max_speed: 84.,
+                spin: 2.,
             },
-            spin: Rotate2D { speed: 1.2, axis: Vec3::Z },
-            wrapping: MovementWrapper,
         }
     }
-    pub fn medium(
-        transform: Transform,
-        space_sheet: &KenneySpriteSheetAsset,
-    ) -> MeteorBundle {
-        let mut rng = rand::thread_rng();
-        let x = rng.gen::<f32>() * METEOR_BASE_SPEED_MEDIUM;
-        let y = rng.gen::<f32>() * METEOR_BASE_SPEED_MEDIUM;
-        let rotation = rng.gen::<f32>() * TAU;
+}
 
-        MeteorBundle {
-            meteor_type: MeteorType::Medium,
-            meteor: Meteor,
-            collider: Collider::circle(21.),
-            sprite_bundle: SpriteBundle {
-                transform,
-                texture: space_sheet.sheet.clone(),
-                ..default()
-            },
-            texture_atlas: TextureAtlas {
-                index: 167,
-                layout: space_sheet
-                    .texture_atlas_layout
-                    .clone(),
-            },
-            linear_movement: LinearMovement {
-                movement_factor: Vec2::new(
-                    x as f32, y as f32,
-                ),
-                movement_direction: Quat::from_rotation_z(
-                    rotation,
-                ),
-            },
-            spin: Rotate2D { speed: 1.6, axis: Vec3::Z },
-            wrapping: MovementWrapper,
+#[derive(Debug, Clone, Deserialize)]
+pub struct MeteorSize {
+    /// SubTexture names on the space sheet
+    pub brown_sprite: String,
+    pub grey_sprite: String,
+    /// Collider radius, in pixels
+    pub radius: f32,
+    /// Fastest drift along each axis, in pixels per
+    /// second
+    pub max_speed: f32,
+    /// Radians per second
+    pub spin: f32,
+}
+
+impl MeteorSize {
+    pub fn sprite_name(&self, material: MeteorMaterial) -> &str {
+        match material {
+            MeteorMaterial::Brown => &self.brown_sprite,
+            MeteorMaterial::Grey => &self.grey_sprite,
         }
     }
-    pub fn small(
+}
+
+/// The level's sprite for the meteor, or the stock one if
+/// the sheet lacks it
+fn meteor_atlas(
+    meteor_type: MeteorType,
+    material: MeteorMaterial,
+    space_sheet: &KenneySpriteSheetAsset,
+    sizes: &MeteorSizes,
+) -> TextureAtlas {
+    space_sheet
+        .atlas(sizes.get(meteor_type).sprite_name(material))
+        .unwrap_or_else(|error| {
+            warn!("{error}, using the stock meteor sprite");
+            space_sheet
+                .atlas(
+                    MeteorSizes::default()
+                        .get(meteor_type)
+                        .sprite_name(material),
+                )
+                .expect("meteor sprite to exist")
+        })
+}
+
+/// A velocity in any direction, up to `max_speed`
+fn random_drift(rng: &mut impl Rng, max_speed: f32) -> Vec2 {
+    Vec2::from_angle(rng.gen_range(0.0..TAU))
+        * rng.gen::<f32>()
+        * max_speed
+}
+
+impl MeteorBundle {
+    /// A meteor of a random material drifting off in a
+    /// random direction
+    pub fn from_type(
+        meteor_type: MeteorType,
         transform: Transform,
         space_sheet: &KenneySpriteSheetAsset,
+        sizes: &MeteorSizes,
     ) -> MeteorBundle {
-        let mut rng = rand::thread_rng();
-        let x = rng.gen::<f32>() * METEOR_BASE_SPEED_SMALL;
-        let y = rng.gen::<f32>() * METEOR_BASE_SPEED_SMALL;
-        let rotation = rng.gen::<f32>() * TAU;
+        let size = sizes.get(meteor_type);
+        let velocity =
+            random_drift(&mut rand::thread_rng(), size.max_speed);
+        let material = MeteorMaterial::random();
 
         MeteorBundle {
-            meteor_type: MeteorType::Small,
+            meteor_type,
+            material,
             meteor: Meteor,
-            collider: Collider::circle(14.),
+            health: Health::new(meteor_type.health()),
+            damage: Damage(meteor_type.collision_damage()),
+            collider: Collider::circle(size.radius),
             sprite_bundle: SpriteBundle {
                 transform,
                 texture: space_sheet.sheet.clone(),
                 ..default()
             },
-            texture_atlas: TextureAtlas {
-                index: 169,
-                layout: space_sheet
-                    .texture_atlas_layout
-                    .clone(),
-            },
-            linear_movement: LinearMovement {
-                movement_factor: Vec2::new(
-                    x as f32, y as f32,
-                ),
-                movement_direction: Quat::from_rotation_z(
-                    rotation,
-                ),
-            },
-            spin: Rotate2D { speed: 2., axis: Vec3::Z },
+            texture_atlas: meteor_atlas(
+                meteor_type,
+                material,
+                space_sheet,
+                sizes,
+            ),
+            body: MeteorBodyBundle::new(velocity, size.spin),
             wrapping: MovementWrapper,
+            in_game: InGame,
         }
     }
+    /// Replaces the randomly rolled material
+    pub fn with_material(
+        mut self,
+        material: MeteorMaterial,
+        space_sheet: &KenneySpriteSheetAsset,
+        sizes: &MeteorSizes,
+    ) -> MeteorBundle {
+        self.material = material;
+        self.texture_atlas = meteor_atlas(
+            self.meteor_type,
+            material,
+            space_sheet,
+            sizes,
+        );
+        self
+    }
+    /// Scales drift and spin, e.g. for later waves
+    pub fn with_speed(
+        mut self,
+        speed_multiplier: f32,
+        spin_multiplier: f32,
+    ) -> MeteorBundle {
+        self.body.velocity.0 *= speed_multiplier;
+        self.body.spin.0 *= spin_multiplier;
+        self
+    }
+    /// Replaces the randomly rolled drift and spin, e.g.
+    /// when restoring a saved meteor
+    pub fn with_movement(
+        mut self,
+        velocity: Vec2,
+        spin: f32,
+    ) -> MeteorBundle {
+        self.body.velocity.0 = velocity;
+        self.body.spin.0 = spin;
+        self
+    }
 }
 
 #[derive(Event)]
 pub struct MeteorDestroyed {
     pub destroyed_at: Transform,
     pub destroyed_type: MeteorType,
+    pub destroyed_material: MeteorMaterial,
+}
+
+/// Breaks meteors that ran out of health
+fn break_meteors(
+    mut commands: Commands,
+    mut events: EventReader<Died>,
+    mut meteor_destroyed: EventWriter<MeteorDestroyed>,
+    meteors: Query<
+        (&Transform, &MeteorType, &MeteorMaterial),
+        With<Meteor>,
+    >,
+) {
+    for Died { entity } in events.read() {
+        let Ok((transform, meteor_type, meteor_material)) =
+            meteors.get(*entity)
+        else {
+            continue;
+        };
+        commands.entity(*entity).despawn_recursive();
+        meteor_destroyed.send(MeteorDestroyed {
+            destroyed_at: *transform,
+            destroyed_type: *meteor_type,
+            destroyed_material: *meteor_material,
+        });
+    }
 }
 
 fn sandbox_meteor_destroyed_event_handler(
     mut commands: Commands,
-    images: Res<ImageAssets>,
     mut events: EventReader<MeteorDestroyed>,
     // meteors: Query<Entity, With<MeteorType>>,
-    sheets: Res<Assets<KenneySpriteSheetAsset>>,
-    mut effect: Query<(
-        &mut EffectProperties,
-        &mut EffectSpawner,
-        &mut Transform,
-    )>,
+    sheets: SpriteSheets,
+    level: CurrentLevel,
+    wave: Res<CurrentWave>,
+    mut effects: Effects,
 ) {
-    let Some(space_sheet) = sheets.get(&images.space_sheet)
-    else {
+    let Some(space_sheet) = sheets.space() else {
         warn!("sandbox_meteor_destroyed_event_handler requires meteor sprites to be loaded");
         return;
     };
-
-    let mut rng = rand::thread_rng();
-    // Note: On first frame where the effect spawns,
-    // EffectSpawner is spawned during PostUpdate,
-    // so will not be available yet. Ignore for a
-    // frame if so.
-    let Ok((
-        mut properties,
-        mut spawner,
-        mut effect_transform,
-    )) = effect.get_single_mut()
-    else {
-        warn!("effect not ready yet, returning");
+    let Some(level) = level.get() else {
+        warn!("sandbox_meteor_destroyed_event_handler requires the level to be loaded");
         return;
     };
 
+    let mut rng = rand::thread_rng();
+
     for MeteorDestroyed {
         destroyed_at,
         destroyed_type,
+        destroyed_material,
     } in &mut events.read()
     {
-        effect_transform.translation =
-            destroyed_at.translation;
-
         let color = Color::lch(
             1.,
             1.,
             rand::random::<f32>() * 360.,
         );
-        properties.set(
-            "spawn_color",
-            color.as_linear_rgba_u32().into(),
+        effects.play(
+            EffectKind::Explosion,
+            destroyed_at.translation,
+            color,
         );
 
-        // Spawn the particles
-        spawner.reset();
-
-        match destroyed_type {
-            MeteorType::Big => {
-                // become two medium
-                for _ in 0..2 {
-                    let x: i32 = rng.gen_range(-5..5);
-                    let y: i32 = rng.gen_range(-5..5);
-                    commands.spawn(MeteorBundle::medium(
-                        Transform::from_xyz(
-                            destroyed_at.translation.x
-                                + x as f32,
-                            destroyed_at.translation.y
-                                + y as f32,
-                            1.,
-                        ),
-                        space_sheet,
-                    ));
-                }
-            }
-            MeteorType::Medium => {
-                // become two smol
-                for _ in 0..2 {
-                    let x: i32 = rng.gen_range(-5..5);
-                    let y: i32 = rng.gen_range(-5..5);
-                    commands.spawn(MeteorBundle::small(
-                        Transform::from_xyz(
-                            destroyed_at.translation.x
-                                + x as f32,
-                            destroyed_at.translation.y
-                                + y as f32,
-                            1.,
-                        ),
-                        space_sheet,
-                    ));
-                }
-            }
-            MeteorType::Small => {
-                // small meteors don't propogate
-                // more meteors
-            }
+        let fragment_type = match destroyed_type {
+            MeteorType::Big => MeteorType::Medium,
+            MeteorType::Medium => MeteorType::Small,
+            // small meteors don't propogate
+            // more meteors
+            MeteorType::Small => continue,
+        };
+        // become two of the next size, side by side
+        // so they don't start out overlapping
+        let spacing =
+            level.meteor_sizes.get(fragment_type).radius + 1.;
+        let axis = Vec2::from_angle(rng.gen::<f32>() * TAU);
+        for side in [-1., 1.] {
+            let offset = axis * side * spacing;
+            commands.spawn(
+                MeteorBundle::from_type(
+                    fragment_type,
+                    Transform::from_translation(
+                        (destroyed_at.translation.xy() + offset)
+                            .extend(1.),
+                    ),
+                    space_sheet,
+                    &level.meteor_sizes,
+                )
+                .with_material(
+                    *destroyed_material,
+                    space_sheet,
+                    &level.meteor_sizes,
+                )
+                .with_speed(
+                    wave.speed_multiplier,
+                    wave.spin_multiplier,
+                ),
+            );
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn drifts_in_every_direction() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let mut quadrants = [false; 4];
+        for _ in 0..100 {
+            let velocity = random_drift(&mut rng, 60.);
+            assert!(velocity.length() <= 60. + f32::EPSILON);
+            let quadrant = match (velocity.x >= 0., velocity.y >= 0.) {
+                (true, true) => 0,
+                (false, true) => 1,
+                (false, false) => 2,
+                (true, false) => 3,
+            };
+            quadrants[quadrant] = true;
+        }
+        assert_eq!(quadrants, [true; 4]);
+    }
+
+    #[test]
+    fn stays_put_without_a_max_speed() {
+        let mut rng = StdRng::seed_from_u64(6);
+        assert_eq!(random_drift(&mut rng, 0.), Vec2::ZERO);
+    }
+}
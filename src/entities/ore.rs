@@ -0,0 +1,285 @@
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    effects::{EffectKind, Effects},
+    entities::meteor::{
+        MeteorDestroyed, MeteorMaterial, MeteorType,
+    },
+    movement::{LinearMovement, MovementWrapper, Rotate2D},
+    utils::{
+        asset_loader::ImageAssets,
+        kenney_asset::KenneySpriteSheetAsset,
+        pause_system::Pausable,
+    },
+    GameState,
+    InGame,
+};
+
+pub struct OrePlugin;
+
+impl Plugin for OrePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Cargo>()
+            .add_event::<OreCollected>()
+            .add_systems(
+                PostUpdate,
+                (
+                    drop_ore,
+                    store_collected_ore,
+                    sparkle_collected_ore,
+                    expire_ore,
+                )
+                    .run_if(resource_equals(
+                        Pausable::NotPaused,
+                    ))
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub enum OreType {
+    Bronze,
+    Silver,
+    Gold,
+}
+
+impl OreType {
+    pub fn sprite_name(&self) -> &'static str {
+        match self {
+            OreType::Bronze => "things_bronze.png",
+            OreType::Silver => "things_silver.png",
+            OreType::Gold => "things_gold.png",
+        }
+    }
+    /// Tint of the sparkle when it is picked up
+    pub fn color(&self) -> Color {
+        match self {
+            OreType::Bronze => Color::rgb(0.9, 0.55, 0.25),
+            OreType::Silver => Color::rgb(0.85, 0.9, 1.),
+            OreType::Gold => Color::rgb(1., 0.85, 0.2),
+        }
+    }
+}
+
+/// A pickup floating in space, worth `amount` of
+/// `ore_type` once the player flies into it
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Ore {
+    pub ore_type: OreType,
+    pub amount: u32,
+}
+
+/// Chance of any ore drop being gold instead of the
+/// meteor material's usual ore
+const GOLD_CHANCE: f64 = 0.1;
+
+impl Ore {
+    /// Rolls the drop for a destroyed meteor. Small
+    /// meteors always drop ore, larger ones only
+    /// sometimes but yield more.
+    pub fn roll(
+        meteor_type: MeteorType,
+        material: MeteorMaterial,
+    ) -> Option<Ore> {
+        let mut rng = rand::thread_rng();
+        let (chance, amount) = match meteor_type {
+            MeteorType::Big => (0.25, 3),
+            MeteorType::Medium => (0.5, 2),
+            MeteorType::Small => (1.0, 1),
+        };
+        if !rng.gen_bool(chance) {
+            return None;
+        }
+        let ore_type = if rng.gen_bool(GOLD_CHANCE) {
+            OreType::Gold
+        } else {
+            match material {
+                MeteorMaterial::Brown => OreType::Bronze,
+                MeteorMaterial::Grey => OreType::Silver,
+            }
+        };
+        Some(Ore { ore_type, amount })
+    }
+}
+
+/// Ore the player has collected this session
+#[derive(
+    Resource, Debug, Clone, Default, Serialize, Deserialize,
+)]
+pub struct Cargo {
+    pub bronze: u32,
+    pub silver: u32,
+    pub gold: u32,
+}
+
+impl Cargo {
+    pub fn add(&mut self, ore: Ore) {
+        match ore.ore_type {
+            OreType::Bronze => self.bronze += ore.amount,
+            OreType::Silver => self.silver += ore.amount,
+            OreType::Gold => self.gold += ore.amount,
+        }
+    }
+
+    pub fn covers(&self, cost: &Cargo) -> bool {
+        self.bronze >= cost.bronze
+            && self.silver >= cost.silver
+            && self.gold >= cost.gold
+    }
+
+    /// Takes `cost` out of the cargo, leaving it untouched
+    /// and returning false when there is not enough
+    pub fn spend(&mut self, cost: &Cargo) -> bool {
+        if !self.covers(cost) {
+            return false;
+        }
+        self.bronze -= cost.bronze;
+        self.silver -= cost.silver;
+        self.gold -= cost.gold;
+        true
+    }
+}
+
+#[derive(Event)]
+pub struct OreCollected {
+    pub ore: Ore,
+    pub at: Vec3,
+}
+
+/// Uncollected ore fades away over the last
+/// [`ORE_FADE_TIME`] of its lifetime and is gone after it
+#[derive(Component)]
+pub struct OreLifetime(Timer);
+
+const ORE_LIFETIME: Duration = Duration::from_secs(12);
+const ORE_FADE_TIME: Duration = Duration::from_secs(3);
+
+#[derive(Bundle)]
+pub struct OreBundle {
+    ore: Ore,
+    lifetime: OreLifetime,
+    collider: Collider,
+    sprite_bundle: SpriteBundle,
+    texture_atlas: TextureAtlas,
+    linear_movement: LinearMovement,
+    spin: Rotate2D,
+    wrapping: MovementWrapper,
+    in_game: InGame,
+}
+
+const ORE_DRIFT_SPEED: f32 = 0.5;
+
+impl OreBundle {
+    pub fn new(
+        ore: Ore,
+        transform: Transform,
+        space_sheet: &KenneySpriteSheetAsset,
+    ) -> OreBundle {
+        let mut rng = rand::thread_rng();
+        let x = rng.gen_range(-1.0..1.0) * ORE_DRIFT_SPEED;
+        let y = rng.gen_range(-1.0..1.0) * ORE_DRIFT_SPEED;
+        let rotation = rng.gen::<f32>() * TAU;
+
+        OreBundle {
+            ore,
+            lifetime: OreLifetime(Timer::new(
+                ORE_LIFETIME,
+                TimerMode::Once,
+            )),
+            collider: Collider::circle(12.),
+            sprite_bundle: SpriteBundle {
+                transform,
+                texture: space_sheet.sheet.clone(),
+                ..default()
+            },
+            texture_atlas: space_sheet
+                .atlas(ore.ore_type.sprite_name())
+                .expect("ore sprite to exist"),
+            linear_movement: LinearMovement {
+                movement_factor: Vec2::new(x, y),
+                movement_direction: Quat::from_rotation_z(
+                    rotation,
+                ),
+            },
+            spin: Rotate2D { speed: 0.8, axis: Vec3::Z },
+            wrapping: MovementWrapper,
+            in_game: InGame,
+        }
+    }
+}
+
+fn drop_ore(
+    mut commands: Commands,
+    images: Res<ImageAssets>,
+    sheets: Res<Assets<KenneySpriteSheetAsset>>,
+    mut events: EventReader<MeteorDestroyed>,
+) {
+    let Some(space_sheet) = sheets.get(&images.space_sheet)
+    else {
+        warn!("drop_ore requires ore sprites to be loaded");
+        return;
+    };
+
+    for MeteorDestroyed {
+        destroyed_at,
+        destroyed_type,
+        destroyed_material,
+    } in events.read()
+    {
+        let Some(ore) =
+            Ore::roll(*destroyed_type, *destroyed_material)
+        else {
+            continue;
+        };
+        commands.spawn(OreBundle::new(
+            ore,
+            Transform::from_translation(
+                destroyed_at.translation,
+            ),
+            space_sheet,
+        ));
+    }
+}
+
+fn store_collected_ore(
+    mut events: EventReader<OreCollected>,
+    mut cargo: ResMut<Cargo>,
+) {
+    for OreCollected { ore, .. } in events.read() {
+        cargo.add(*ore);
+        debug!("Collected {:?}, cargo: {:?}", ore, *cargo);
+    }
+}
+
+fn sparkle_collected_ore(
+    mut events: EventReader<OreCollected>,
+    mut effects: Effects,
+) {
+    for OreCollected { ore, at } in events.read() {
+        effects.play(EffectKind::OreSparkle, *at, ore.ore_type.color());
+    }
+}
+
+fn expire_ore(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut ores: Query<(Entity, &mut OreLifetime, &mut Sprite)>,
+) {
+    for (entity, mut lifetime, mut sprite) in &mut ores {
+        if lifetime.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let remaining = lifetime.0.remaining_secs()
+            / ORE_FADE_TIME.as_secs_f32();
+        sprite.color.set_a(remaining.min(1.));
+    }
+}
@@ -0,0 +1,491 @@
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::{prelude::*, utils::HashMap};
+use bevy_xpbd_2d::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    animation::{AnimationMode, SpriteAnimation},
+    effects::{EffectKind, Effects},
+    entities::{
+        lives::Lives,
+        meteor::{MeteorDestroyed, MeteorType},
+    },
+    movement::{LinearMovement, MovementWrapper, Rotate2D},
+    utils::{
+        asset_loader::{FontAssets, ImageAssets},
+        kenney_asset::KenneySpriteSheetAsset,
+        pause_system::Pausable,
+    },
+    GameState, InGame, Player,
+};
+
+pub struct PowerUpPlugin;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PowerUpCollected>()
+            .add_event::<ShieldHit>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                spawn_power_up_hud,
+            )
+            .add_systems(
+                PostUpdate,
+                (drop_power_ups, ripple_shield, expire_power_ups)
+                    .run_if(resource_equals(
+                        Pausable::NotPaused,
+                    ))
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (
+                    apply_power_ups,
+                    tick_power_ups,
+                    render_shield_bubble,
+                    render_power_up_hud,
+                )
+                    .chain()
+                    .run_if(resource_equals(
+                        Pausable::NotPaused,
+                    ))
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PowerUpKind {
+    /// Absorbs the next hit
+    Shield,
+    /// Faster fire rate
+    Bolt,
+    /// Score multiplier
+    Star,
+    /// An extra life, up to [`MAX_LIVES`]
+    Pill,
+}
+
+impl PowerUpKind {
+    pub fn all_kinds() -> [PowerUpKind; 4] {
+        [
+            PowerUpKind::Shield,
+            PowerUpKind::Bolt,
+            PowerUpKind::Star,
+            PowerUpKind::Pill,
+        ]
+    }
+    pub fn sprite_name(&self) -> &'static str {
+        match self {
+            PowerUpKind::Shield => "powerupBlue_shield.png",
+            PowerUpKind::Bolt => "powerupYellow_bolt.png",
+            PowerUpKind::Star => "powerupGreen_star.png",
+            PowerUpKind::Pill => "pill_red.png",
+        }
+    }
+    /// How long the effect lasts, `None` for the ones
+    /// that apply at once
+    pub fn duration(&self) -> Option<Duration> {
+        match self {
+            PowerUpKind::Shield => Some(Duration::from_secs(15)),
+            PowerUpKind::Bolt => Some(Duration::from_secs(8)),
+            PowerUpKind::Star => Some(Duration::from_secs(10)),
+            PowerUpKind::Pill => None,
+        }
+    }
+}
+
+/// A pickup floating in space
+#[derive(Component, Debug, Clone, Copy)]
+pub struct PowerUp(pub PowerUpKind);
+
+#[derive(Event)]
+pub struct PowerUpCollected {
+    pub kind: PowerUpKind,
+}
+
+/// The shield took a hit for the ship at `at`
+#[derive(Event)]
+pub struct ShieldHit {
+    pub at: Vec3,
+}
+
+/// Timed power-ups running on the player's ship, they are
+/// lost with the ship
+#[derive(Component, Debug, Default)]
+pub struct PowerUpEffects {
+    pub remaining: HashMap<PowerUpKind, Timer>,
+}
+
+impl PowerUpEffects {
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.remaining.contains_key(&kind)
+    }
+    /// Spends the shield on a hit, returning whether there
+    /// was one to take it
+    pub fn absorb_hit(&mut self) -> bool {
+        self.remaining.remove(&PowerUpKind::Shield).is_some()
+    }
+}
+
+/// Bolt divides the weapon cooldown by this
+pub const BOLT_FIRE_RATE: u32 = 2;
+/// Star multiplies points by this
+pub const STAR_MULTIPLIER: u32 = 2;
+/// Pills do nothing past this many lives
+pub const MAX_LIVES: usize = 5;
+
+/// Power-ups left floating fade away over the last
+/// [`POWER_UP_FADE_TIME`] of their lifetime
+#[derive(Component)]
+pub struct PowerUpLifetime(Timer);
+
+const POWER_UP_LIFETIME: Duration = Duration::from_secs(15);
+const POWER_UP_FADE_TIME: Duration = Duration::from_secs(3);
+
+#[derive(Bundle)]
+pub struct PowerUpBundle {
+    power_up: PowerUp,
+    lifetime: PowerUpLifetime,
+    collider: Collider,
+    sprite_bundle: SpriteBundle,
+    texture_atlas: TextureAtlas,
+    linear_movement: LinearMovement,
+    spin: Rotate2D,
+    wrapping: MovementWrapper,
+    in_game: InGame,
+}
+
+const POWER_UP_DRIFT_SPEED: f32 = 0.4;
+
+impl PowerUpBundle {
+    pub fn new(
+        kind: PowerUpKind,
+        transform: Transform,
+        space_sheet: &KenneySpriteSheetAsset,
+    ) -> PowerUpBundle {
+        let mut rng = rand::thread_rng();
+        let x = rng.gen_range(-1.0..1.0) * POWER_UP_DRIFT_SPEED;
+        let y = rng.gen_range(-1.0..1.0) * POWER_UP_DRIFT_SPEED;
+        let rotation = rng.gen::<f32>() * TAU;
+
+        PowerUpBundle {
+            power_up: PowerUp(kind),
+            lifetime: PowerUpLifetime(Timer::new(
+                POWER_UP_LIFETIME,
+                TimerMode::Once,
+            )),
+            collider: Collider::circle(16.),
+            sprite_bundle: SpriteBundle {
+                transform,
+                texture: space_sheet.sheet.clone(),
+                ..default()
+            },
+            texture_atlas: space_sheet
+                .atlas(kind.sprite_name())
+                .expect("power-up sprite to exist"),
+            linear_movement: LinearMovement {
+                movement_factor: Vec2::new(x, y),
+                movement_direction: Quat::from_rotation_z(
+                    rotation,
+                ),
+            },
+            spin: Rotate2D { speed: 0.3, axis: Vec3::Z },
+            wrapping: MovementWrapper,
+            in_game: InGame,
+        }
+    }
+}
+
+/// Bigger meteors are likelier to hold a power-up
+fn drop_chance(meteor_type: MeteorType) -> f64 {
+    match meteor_type {
+        MeteorType::Big => 0.15,
+        MeteorType::Medium => 0.1,
+        MeteorType::Small => 0.05,
+    }
+}
+
+fn drop_power_ups(
+    mut commands: Commands,
+    images: Res<ImageAssets>,
+    sheets: Res<Assets<KenneySpriteSheetAsset>>,
+    mut events: EventReader<MeteorDestroyed>,
+) {
+    let Some(space_sheet) = sheets.get(&images.space_sheet)
+    else {
+        warn!("drop_power_ups requires power-up sprites to be loaded");
+        return;
+    };
+
+    let mut rng = rand::thread_rng();
+    for MeteorDestroyed {
+        destroyed_at,
+        destroyed_type,
+        ..
+    } in events.read()
+    {
+        if !rng.gen_bool(drop_chance(*destroyed_type)) {
+            continue;
+        }
+        let kind = *PowerUpKind::all_kinds()
+            .choose(&mut rng)
+            .expect("power-up kinds not to be empty");
+        commands.spawn(PowerUpBundle::new(
+            kind,
+            Transform::from_translation(
+                destroyed_at.translation,
+            ),
+            space_sheet,
+        ));
+    }
+}
+
+fn expire_power_ups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut power_ups: Query<(
+        Entity,
+        &mut PowerUpLifetime,
+        &mut Sprite,
+    )>,
+) {
+    for (entity, mut lifetime, mut sprite) in &mut power_ups {
+        if lifetime.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let remaining = lifetime.0.remaining_secs()
+            / POWER_UP_FADE_TIME.as_secs_f32();
+        sprite.color.set_a(remaining.min(1.));
+    }
+}
+
+/// Picking up a running power-up starts its timer over
+fn apply_power_ups(
+    mut commands: Commands,
+    mut events: EventReader<PowerUpCollected>,
+    mut lives: ResMut<Lives>,
+    mut player: Query<
+        (Entity, Option<&mut PowerUpEffects>),
+        With<Player>,
+    >,
+) {
+    let Ok((entity, mut effects)) = player.get_single_mut()
+    else {
+        return;
+    };
+    let mut added = PowerUpEffects::default();
+    for PowerUpCollected { kind } in events.read() {
+        let Some(duration) = kind.duration() else {
+            lives.0 = (lives.0 + 1).min(MAX_LIVES);
+            continue;
+        };
+        let effects = match effects.as_deref_mut() {
+            Some(effects) => effects,
+            None => &mut added,
+        };
+        effects
+            .remaining
+            .insert(*kind, Timer::new(duration, TimerMode::Once));
+    }
+    if !added.remaining.is_empty() {
+        commands.entity(entity).insert(added);
+    }
+}
+
+fn tick_power_ups(
+    time: Res<Time>,
+    mut effects: Query<&mut PowerUpEffects>,
+) {
+    for mut effects in &mut effects {
+        effects.remaining.retain(|_, timer| {
+            !timer.tick(time.delta()).finished()
+        });
+    }
+}
+
+const SHIELD_BUBBLE_FPS: f32 = 6.;
+
+#[derive(Component)]
+struct ShieldBubble;
+
+/// Shows the shield bubble around the ship for as long as
+/// the shield holds
+fn render_shield_bubble(
+    mut commands: Commands,
+    images: Res<ImageAssets>,
+    sheets: Res<Assets<KenneySpriteSheetAsset>>,
+    ships: Query<(Entity, &PowerUpEffects), With<Player>>,
+    bubbles: Query<(Entity, &Parent), With<ShieldBubble>>,
+) {
+    for (ship, effects) in &ships {
+        let bubble = bubbles
+            .iter()
+            .find(|(_, parent)| parent.get() == ship)
+            .map(|(bubble, _)| bubble);
+        match (effects.is_active(PowerUpKind::Shield), bubble) {
+            (true, None) => {
+                let Some(space_sheet) =
+                    sheets.get(&images.space_sheet)
+                else {
+                    return;
+                };
+                let animation = SpriteAnimation::from_prefix(
+                    space_sheet,
+                    "shield",
+                    SHIELD_BUBBLE_FPS,
+                    AnimationMode::PingPong { cycles: None },
+                )
+                .expect("shield frames to exist");
+                let bubble = commands
+                    .spawn((
+                        SpriteBundle {
+                            transform: Transform::from_xyz(
+                                0., 0., 2.,
+                            ),
+                            texture: space_sheet.sheet.clone(),
+                            sprite: Sprite {
+                                color: Color::rgba(
+                                    1., 1., 1., 0.5,
+                                ),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        animation.atlas(space_sheet),
+                        animation,
+                        ShieldBubble,
+                    ))
+                    .id();
+                commands.entity(ship).add_child(bubble);
+            }
+            (false, Some(bubble)) => {
+                commands.entity(bubble).despawn_recursive();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// One HUD row per timed power-up, shown while it runs
+#[derive(Component)]
+struct PowerUpIcon(PowerUpKind);
+
+#[derive(Component)]
+struct PowerUpTimeLeft(PowerUpKind);
+
+fn spawn_power_up_hud(
+    mut commands: Commands,
+    images: Res<ImageAssets>,
+    fonts: Res<FontAssets>,
+    sheets: Res<Assets<KenneySpriteSheetAsset>>,
+) {
+    let space_sheet = sheets.get(&images.space_sheet).unwrap();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(20.),
+                    top: Val::Px(100.),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(6.),
+                    ..default()
+                },
+                ..default()
+            },
+            InGame,
+        ))
+        .with_children(|parent| {
+            for kind in PowerUpKind::all_kinds() {
+                if kind.duration().is_none() {
+                    continue;
+                }
+                parent
+                    .spawn((
+                        NodeBundle {
+                            style: Style {
+                                align_items: AlignItems::Center,
+                                column_gap: Val::Px(8.),
+                                display: Display::None,
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        PowerUpIcon(kind),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            ImageBundle {
+                                style: Style {
+                                    width: Val::Px(24.),
+                                    height: Val::Px(24.),
+                                    ..default()
+                                },
+                                image: space_sheet
+                                    .sheet
+                                    .clone()
+                                    .into(),
+                                ..default()
+                            },
+                            space_sheet
+                                .atlas(kind.sprite_name())
+                                .expect("power-up sprite to exist"),
+                        ));
+                        parent.spawn((
+                            TextBundle::from_section(
+                                "",
+                                TextStyle {
+                                    font: fonts.roboto.clone(),
+                                    font_size: 18.0,
+                                    color: Color::WHITE,
+                                },
+                            ),
+                            PowerUpTimeLeft(kind),
+                        ));
+                    });
+            }
+        });
+}
+
+fn render_power_up_hud(
+    player: Query<&PowerUpEffects, With<Player>>,
+    mut icons: Query<(&PowerUpIcon, &mut Style)>,
+    mut times: Query<(&PowerUpTimeLeft, &mut Text)>,
+) {
+    let effects = player.get_single().ok();
+    let timer = |kind: &PowerUpKind| {
+        effects.and_then(|effects| effects.remaining.get(kind))
+    };
+    for (PowerUpIcon(kind), mut style) in &mut icons {
+        let display = if timer(kind).is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if style.display != display {
+            style.display = display;
+        }
+    }
+    for (PowerUpTimeLeft(kind), mut text) in &mut times {
+        if let Some(timer) = timer(kind) {
+            text.sections[0].value = format!(
+                "{:.0}s",
+                timer.remaining_secs().ceil()
+            );
+        }
+    }
+}
+
+const SHIELD_RIPPLE_COLOR: Color = Color::rgb(0.4, 0.7, 1.);
+
+fn ripple_shield(
+    mut events: EventReader<ShieldHit>,
+    mut effects: Effects,
+) {
+    for ShieldHit { at } in events.read() {
+        effects.play(EffectKind::ShieldRipple, *at, SHIELD_RIPPLE_COLOR);
+    }
+}
@@ -0,0 +1,301 @@
+use std::time::Duration;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    entities::{
+        boss::BossDefeated,
+        enemies::EnemyDestroyed,
+        meteor::MeteorDestroyed,
+        powerups::{PowerUpEffects, PowerUpKind, STAR_MULTIPLIER},
+    },
+    utils::{
+        asset_loader::SpriteSheets,
+        kenney_asset::KenneySpriteSheetAsset,
+        pause_system::Pausable,
+        save_system::{read_high_score, write_high_score},
+    },
+    GameState, Player,
+};
+
+pub struct ScorePlugin;
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .init_resource::<Combo>()
+            .add_systems(Startup, load_high_score)
+            .add_systems(
+                PostUpdate,
+                award_points
+                    .run_if(resource_equals(
+                        Pausable::NotPaused,
+                    ))
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (
+                    tick_combo.run_if(resource_equals(
+                        Pausable::NotPaused,
+                    )),
+                    render_score,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                OnEnter(GameState::Playing),
+                spawn_score_ui,
+            )
+            .add_systems(
+                OnExit(GameState::Playing),
+                (remove_score_ui, store_high_score),
+            );
+    }
+}
+
+#[derive(Debug, Resource, Default, PartialEq, Eq)]
+pub struct Score(pub u32);
+
+/// Best score ever reached, persisted between runs
+#[derive(Debug, Resource, Default, PartialEq, Eq)]
+pub struct HighScore(pub u32);
+
+/// Kills in quick succession raise the multiplier applied
+/// to every kill, up to [`MAX_COMBO`]. Letting the window
+/// run out resets it.
+#[derive(Debug, Resource)]
+pub struct Combo {
+    pub multiplier: u32,
+    pub window: Timer,
+}
+
+const COMBO_WINDOW: Duration = Duration::from_millis(1500);
+const MAX_COMBO: u32 = 5;
+
+impl Default for Combo {
+    fn default() -> Self {
+        let mut window =
+            Timer::new(COMBO_WINDOW, TimerMode::Once);
+        window.tick(COMBO_WINDOW);
+        Combo {
+            multiplier: 1,
+            window,
+        }
+    }
+}
+
+
+fn load_high_score(mut commands: Commands) {
+    let high_score = read_high_score().unwrap_or_else(|error| {
+        warn!("Could not read high score: {error}");
+        0
+    });
+    commands.insert_resource(HighScore(high_score));
+}
+
+fn store_high_score(high_score: Res<HighScore>) {
+    if let Err(error) = write_high_score(high_score.0) {
+        error!("Could not store high score: {error}");
+    }
+}
+
+fn award_points(
+    mut meteors: EventReader<MeteorDestroyed>,
+    mut enemies: EventReader<EnemyDestroyed>,
+    mut bosses: EventReader<BossDefeated>,
+    mut score: ResMut<Score>,
+    mut high_score: ResMut<HighScore>,
+    mut combo: ResMut<Combo>,
+    player: Query<&PowerUpEffects, With<Player>>,
+) {
+    let star = if player.get_single().is_ok_and(|effects| {
+        effects.is_active(PowerUpKind::Star)
+    }) {
+        STAR_MULTIPLIER
+    } else {
+        1
+    };
+    let kills = meteors
+        .read()
+        .map(|meteor| meteor.destroyed_type.points())
+        .chain(enemies.read().map(|enemy| enemy.kind.points()))
+        .chain(bosses.read().map(|boss| boss.points));
+    for points in kills {
+        combo.multiplier = if combo.window.finished() {
+            1
+        } else {
+            (combo.multiplier + 1).min(MAX_COMBO)
+        };
+        combo.window.reset();
+
+        score.0 += points * combo.multiplier * star;
+        if score.0 > high_score.0 {
+            high_score.0 = score.0;
+        }
+    }
+}
+
+fn tick_combo(time: Res<Time>, mut combo: ResMut<Combo>) {
+    combo.window.tick(time.delta());
+    if combo.window.just_finished() {
+        combo.multiplier = 1;
+    }
+}
+
+#[derive(Component)]
+struct ScoreContainer;
+
+#[derive(Component)]
+struct ScoreDigits;
+
+#[derive(Component)]
+struct HighScoreDigits;
+
+fn spawn_score_ui(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(0.),
+                    padding: UiRect::all(Val::Px(20.)),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::FlexEnd,
+                    row_gap: Val::Px(6.),
+                    ..default()
+                },
+                ..default()
+            },
+            ScoreContainer,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(2.),
+                        ..default()
+                    },
+                    ..default()
+                },
+                ScoreDigits,
+            ));
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(1.),
+                        ..default()
+                    },
+                    ..default()
+                },
+                HighScoreDigits,
+            ));
+        });
+}
+
+fn remove_score_ui(
+    mut commands: Commands,
+    query: Query<Entity, With<ScoreContainer>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Spawns one numeral glyph per digit of `value` (and
+/// `X` followed by the multiplier when it is above one)
+/// under `parent`
+fn spawn_numerals(
+    parent: &mut ChildBuilder,
+    space_sheet: &KenneySpriteSheetAsset,
+    value: u32,
+    multiplier: u32,
+    size: Val,
+) {
+    let mut glyphs = value.to_string();
+    if multiplier > 1 {
+        glyphs.push('X');
+        glyphs.push_str(&multiplier.to_string());
+    }
+    for glyph in glyphs.chars() {
+        parent.spawn((
+            ImageBundle {
+                style: Style {
+                    width: size,
+                    height: size,
+                    ..default()
+                },
+                image: space_sheet.sheet.clone().into(),
+                ..default()
+            },
+            space_sheet
+                .atlas(&format!("numeral{glyph}.png"))
+                .expect("numeral sprite to exist"),
+        ));
+    }
+}
+
+/// What the score hud shows
+#[derive(SystemParam)]
+struct ScoreReadout<'w> {
+    score: Res<'w, Score>,
+    high_score: Res<'w, HighScore>,
+    combo: Res<'w, Combo>,
+}
+
+fn render_score(
+    mut commands: Commands,
+    sheets: SpriteSheets,
+    readout: ScoreReadout,
+    score_digits: Query<(Entity, Ref<ScoreDigits>)>,
+    high_score_digits: Query<Entity, With<HighScoreDigits>>,
+    mut rendered: Local<Option<(u32, u32, u32)>>,
+) {
+    let ScoreReadout {
+        score,
+        high_score,
+        combo,
+    } = readout;
+    let state = (score.0, high_score.0, combo.multiplier);
+    let (
+        Ok((score_entity, score_container)),
+        Ok(high_score_entity),
+    ) = (score_digits.get_single(), high_score_digits.get_single())
+    else {
+        return;
+    };
+    // the hud is rebuilt every time play starts
+    if *rendered == Some(state) && !score_container.is_added() {
+        return;
+    }
+    let Some(space_sheet) = sheets.space() else {
+        warn!("render_score requires numeral sprites to be loaded");
+        return;
+    };
+    *rendered = Some(state);
+
+    commands
+        .entity(score_entity)
+        .despawn_descendants()
+        .with_children(|parent| {
+            spawn_numerals(
+                parent,
+                space_sheet,
+                score.0,
+                combo.multiplier,
+                Val::Px(24.),
+            );
+        });
+    commands
+        .entity(high_score_entity)
+        .despawn_descendants()
+        .with_children(|parent| {
+            spawn_numerals(
+                parent,
+                space_sheet,
+                high_score.0,
+                1,
+                Val::Px(14.),
+            );
+        });
+}
@@ -0,0 +1,612 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    animation::{AnimationMode, SpriteAnimation},
+    entities::{
+        health::{Health, HitGrace},
+        spaceship::{
+            spawn_player_ship, BaseShipSpeed, EngineFire,
+            ShipBodyBundle, ShipColor, ShipLevels, ShipStats,
+            WeaponLoadout, SHIP_HIT_GRACE,
+        },
+    },
+    gameui::settings::GameSettings,
+    utils::kenney_asset::KenneySpriteSheetAsset,
+    InGame, Player,
+};
+
+pub struct ShipBuilderPlugin;
+
+impl Plugin for ShipBuilderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, sum_part_stats);
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub enum PartKind {
+    Cockpit,
+    Wing,
+    Engine,
+    Gun,
+}
+
+impl PartKind {
+    /// How many variants of this part the sheet has
+    pub fn variants(&self) -> u8 {
+        match self {
+            PartKind::Cockpit | PartKind::Wing => 8,
+            PartKind::Engine => 5,
+            PartKind::Gun => 11,
+        }
+    }
+}
+
+/// Colors cockpits and wings come in
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub enum PartColor {
+    #[default]
+    Blue,
+    Green,
+    Red,
+    Yellow,
+}
+
+impl PartColor {
+    pub fn all_colors() -> [PartColor; 4] {
+        [
+            PartColor::Blue,
+            PartColor::Green,
+            PartColor::Red,
+            PartColor::Yellow,
+        ]
+    }
+    fn name(&self) -> &'static str {
+        match self {
+            PartColor::Blue => "Blue",
+            PartColor::Green => "Green",
+            PartColor::Red => "Red",
+            PartColor::Yellow => "Yellow",
+        }
+    }
+    /// The hull color closest to this one, for life icons
+    fn ship_color(&self) -> ShipColor {
+        match self {
+            PartColor::Blue => ShipColor::Blue,
+            PartColor::Green => ShipColor::Green,
+            PartColor::Red => ShipColor::Red,
+            PartColor::Yellow => ShipColor::Orange,
+        }
+    }
+}
+
+/// What one part adds to the ship it is mounted on
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PartStats {
+    pub mass: f32,
+    /// Pushes the ship forward, see [`ShipStats`]
+    pub thrust: f32,
+    /// Degrees per second at a mass of [`REFERENCE_MASS`]
+    pub turn_rate: f32,
+    pub hardpoints: u32,
+}
+
+/// A ship assembled from sheet parts: one cockpit, a
+/// mirrored pair of wings, an engine and guns on the
+/// wings. Each field picks the part's variant.
+#[derive(
+    Component, Debug, Clone, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct ShipDesign {
+    pub name: String,
+    pub color: PartColor,
+    pub cockpit: u8,
+    pub wing: u8,
+    pub engine: u8,
+    pub gun: u8,
+}
+
+impl Default for ShipDesign {
+    fn default() -> Self {
+        ShipDesign {
+            name: "New Design".to_string(),
+            color: PartColor::Blue,
+            cockpit: 0,
+            wing: 0,
+            engine: 0,
+            gun: 0,
+        }
+    }
+}
+
+/// Mass at which thrust and turn rate are taken as is,
+/// heavier ships are slower
+const REFERENCE_MASS: f32 = 6.;
+const MIN_MOVEMENT_SPEED: f32 = 150.;
+const MAX_MOVEMENT_SPEED: f32 = 550.;
+const BASE_COOLDOWN_MILLIS: u64 = 220;
+
+impl ShipDesign {
+    pub fn variant(&self, kind: PartKind) -> u8 {
+        match kind {
+            PartKind::Cockpit => self.cockpit,
+            PartKind::Wing => self.wing,
+            PartKind::Engine => self.engine,
+            PartKind::Gun => self.gun,
+        }
+    }
+
+    /// Whether every part picks a variant the sheet has
+    pub fn is_valid(&self) -> bool {
+        [
+            PartKind::Cockpit,
+            PartKind::Wing,
+            PartKind::Engine,
+            PartKind::Gun,
+        ]
+        .iter()
+        .all(|kind| self.variant(*kind) < kind.variants())
+    }
+
+    /// Steps the variant of `kind` by `step`, wrapping
+    /// around the available parts
+    pub fn cycle(&mut self, kind: PartKind, step: i32) {
+        let variants = kind.variants() as i32;
+        let next = (self.variant(kind) as i32 + step)
+            .rem_euclid(variants) as u8;
+        match kind {
+            PartKind::Cockpit => self.cockpit = next,
+            PartKind::Wing => self.wing = next,
+            PartKind::Engine => self.engine = next,
+            PartKind::Gun => self.gun = next,
+        }
+    }
+
+    pub fn sprite_name(&self, kind: PartKind) -> String {
+        let variant = self.variant(kind);
+        match kind {
+            PartKind::Cockpit => format!(
+                "cockpit{}_{variant}.png",
+                self.color.name()
+            ),
+            PartKind::Wing => {
+                format!("wing{}_{variant}.png", self.color.name())
+            }
+            PartKind::Engine => {
+                format!("engine{}.png", variant + 1)
+            }
+            PartKind::Gun => format!("gun{variant:02}.png"),
+        }
+    }
+
+    pub fn part_stats(&self, kind: PartKind) -> PartStats {
+        let variant = self.variant(kind) as f32;
+        match kind {
+            // the nose always carries one laser
+            PartKind::Cockpit => PartStats {
+                mass: 2. + 0.25 * variant,
+                turn_rate: 60. + 15. * variant,
+                hardpoints: 1,
+                ..default()
+            },
+            PartKind::Wing => PartStats {
+                mass: 1. + 0.25 * variant,
+                turn_rate: 150. - 10. * variant,
+                ..default()
+            },
+            PartKind::Engine => PartStats {
+                mass: 1.5 + 0.5 * variant,
+                thrust: 225. + 75. * variant,
+                ..default()
+            },
+            // only the bigger guns carry a laser of their own
+            PartKind::Gun => PartStats {
+                mass: 0.25 + 0.05 * variant,
+                hardpoints: u32::from(self.gun >= 5),
+                ..default()
+            },
+        }
+    }
+
+    /// What the assembled ship flies like, the same sum
+    /// [`sum_part_stats`] makes when parts are swapped
+    pub fn stats(&self) -> ShipStats {
+        let parts = [
+            self.part_stats(PartKind::Cockpit),
+            self.part_stats(PartKind::Wing),
+            self.part_stats(PartKind::Wing),
+            self.part_stats(PartKind::Engine),
+            self.part_stats(PartKind::Gun),
+            self.part_stats(PartKind::Gun),
+        ];
+        ship_stats(&parts, self.gun)
+    }
+}
+
+/// Turns part stats into what movement and weapons use.
+/// Bigger guns also fire faster.
+fn ship_stats(parts: &[PartStats], gun: u8) -> ShipStats {
+    let total = parts.iter().fold(
+        PartStats::default(),
+        |total, part| PartStats {
+            mass: total.mass + part.mass,
+            thrust: total.thrust + part.thrust,
+            turn_rate: total.turn_rate + part.turn_rate,
+            hardpoints: total.hardpoints + part.hardpoints,
+        },
+    );
+    let mass_factor = REFERENCE_MASS / total.mass.max(1.);
+    ShipStats {
+        speed: BaseShipSpeed {
+            movement_speed: (total.thrust * mass_factor)
+                .clamp(MIN_MOVEMENT_SPEED, MAX_MOVEMENT_SPEED),
+            rotation_speed: (total.turn_rate * mass_factor)
+                .to_radians(),
+        },
+        loadout: WeaponLoadout {
+            cooldown: Duration::from_millis(
+                BASE_COOLDOWN_MILLIS - 8 * gun as u64,
+            ),
+            lasers: total.hardpoints.max(1),
+        },
+    }
+}
+
+/// One piece of a designed ship, a child of the ship
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ShipPart {
+    pub stats: PartStats,
+}
+
+/// Where each part sits relative to the cockpit's
+/// center, `flip` mirrors the left wing
+pub struct PartPlacement {
+    pub kind: PartKind,
+    pub offset: Vec3,
+    pub flip: bool,
+}
+
+impl ShipDesign {
+    /// Lays the parts out around the cockpit using their
+    /// sizes on the sheet
+    pub fn layout(
+        &self,
+        space_sheet: &KenneySpriteSheetAsset,
+    ) -> Vec<PartPlacement> {
+        let size = |kind| {
+            space_sheet
+                .size_of(&self.sprite_name(kind))
+                .expect("ship part sprite to exist")
+        };
+        let cockpit = size(PartKind::Cockpit);
+        let wing = size(PartKind::Wing);
+        let engine = size(PartKind::Engine);
+        let gun = size(PartKind::Gun);
+
+        // parts overlap a little so no gaps show
+        let wing_x = (cockpit.x + wing.x) / 2. - 6.;
+        let engine_y = -(cockpit.y + engine.y) / 2. + 8.;
+        let gun_y = wing.y / 2. - gun.y / 4.;
+        let mut placements = vec![
+            PartPlacement {
+                kind: PartKind::Engine,
+                offset: Vec3::new(0., engine_y, 0.1),
+                flip: false,
+            },
+            PartPlacement {
+                kind: PartKind::Wing,
+                offset: Vec3::new(-wing_x, 0., 0.2),
+                flip: true,
+            },
+            PartPlacement {
+                kind: PartKind::Wing,
+                offset: Vec3::new(wing_x, 0., 0.2),
+                flip: false,
+            },
+            PartPlacement {
+                kind: PartKind::Cockpit,
+                offset: Vec3::new(0., 0., 0.4),
+                flip: false,
+            },
+        ];
+        placements.extend([-1., 1.].map(|side| PartPlacement {
+            kind: PartKind::Gun,
+            offset: Vec3::new(side * wing_x, gun_y, 0.3),
+            flip: side < 0.,
+        }));
+        placements
+    }
+
+    /// Half the distance between the wing tips
+    fn half_span(&self, space_sheet: &KenneySpriteSheetAsset) -> f32 {
+        let size = |kind| {
+            space_sheet
+                .size_of(&self.sprite_name(kind))
+                .expect("ship part sprite to exist")
+        };
+        size(PartKind::Cockpit).x / 2. + size(PartKind::Wing).x - 6.
+    }
+}
+
+const ENGINE_FIRE_FPS: f32 = 24.;
+
+/// Spawns a ship assembled from `design` as a parent
+/// entity with one child per part
+pub fn spawn_designed_ship(
+    commands: &mut Commands,
+    space_sheet: &KenneySpriteSheetAsset,
+    design: &ShipDesign,
+    transform: Transform,
+) -> Entity {
+    let half_span = design.half_span(space_sheet);
+    let ship = commands
+        .spawn((
+            SpatialBundle::from_transform(transform),
+            Player,
+            // designed ships do not take hull upgrades
+            ShipLevels::Initial,
+            design.color.ship_color(),
+            design.clone(),
+            design.stats(),
+            Health::new(ShipLevels::Initial.max_health()),
+            HitGrace(SHIP_HIT_GRACE),
+            Collider::capsule_endpoints(
+                Vec2::new(-half_span + 20., 0.),
+                Vec2::new(half_span - 20., 0.),
+                20.,
+            ),
+            ShipBodyBundle::default(),
+            InGame,
+        ))
+        .id();
+
+    for placement in design.layout(space_sheet) {
+        let part = commands
+            .spawn((
+                SpriteBundle {
+                    transform: Transform::from_translation(
+                        placement.offset,
+                    ),
+                    texture: space_sheet.sheet.clone(),
+                    sprite: Sprite {
+                        flip_x: placement.flip,
+                        ..default()
+                    },
+                    ..default()
+                },
+                space_sheet
+                    .atlas(&design.sprite_name(placement.kind))
+                    .expect("ship part sprite to exist"),
+                ShipPart {
+                    stats: design.part_stats(placement.kind),
+                },
+            ))
+            .id();
+        commands.entity(ship).add_child(part);
+
+        if placement.kind == PartKind::Engine {
+            let animation = SpriteAnimation::from_prefix(
+                space_sheet,
+                "fire",
+                ENGINE_FIRE_FPS,
+                AnimationMode::Loop,
+            )
+            .expect("engine fire frames to exist");
+            let fire = commands
+                .spawn((
+                    SpriteBundle {
+                        transform: Transform::from_xyz(
+                            0., -30., -0.05,
+                        ),
+                        texture: space_sheet.sheet.clone(),
+                        sprite: Sprite {
+                            flip_y: true,
+                            ..default()
+                        },
+                        visibility: Visibility::Hidden,
+                        ..default()
+                    },
+                    animation.atlas(space_sheet),
+                    animation,
+                    EngineFire,
+                ))
+                .id();
+            commands.entity(part).add_child(fire);
+        }
+    }
+    ship
+}
+
+/// Spawns the ship picked in the menus: the active
+/// design if there is one, otherwise the hangar hull at
+/// `level`
+pub fn spawn_selected_ship(
+    commands: &mut Commands,
+    space_sheet: &KenneySpriteSheetAsset,
+    settings: &GameSettings,
+    level: ShipLevels,
+    transform: Transform,
+) -> Entity {
+    match &settings.design {
+        Some(design) => spawn_designed_ship(
+            commands,
+            space_sheet,
+            design,
+            transform,
+        ),
+        None => spawn_player_ship(
+            commands,
+            space_sheet,
+            level,
+            settings.ship_color,
+            transform,
+        ),
+    }
+}
+
+/// Keeps a designed ship's [`ShipStats`] in line with the
+/// parts mounted on it once they change after spawning
+fn sum_part_stats(
+    mut commands: Commands,
+    ships: Query<
+        (Entity, &ShipDesign, Ref<Children>),
+        Changed<Children>,
+    >,
+    parts: Query<&ShipPart>,
+) {
+    for (entity, design, children) in &ships {
+        // spawn_designed_ship already set the stats
+        if children.is_added() {
+            continue;
+        }
+        let stats: Vec<PartStats> = parts
+            .iter_many(&*children)
+            .map(|part| part.stats)
+            .collect();
+        commands
+            .entity(entity)
+            .insert(ship_stats(&stats, design.gun));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::HashMap;
+
+    use super::*;
+    use crate::utils::kenney_asset::SubTexture;
+
+    /// A sheet holding just the parts of `design`, each
+    /// `size` pixels
+    fn sheet_for(
+        design: &ShipDesign,
+        sizes: [(PartKind, UVec2); 4],
+    ) -> KenneySpriteSheetAsset {
+        let textures: Vec<SubTexture> = sizes
+            .iter()
+            .map(|(kind, size)| SubTexture {
+                name: design.sprite_name(*kind),
+                x: 0,
+                y: 0,
+                width: size.x,
+                height: size.y,
+            })
+            .collect();
+        let indices: HashMap<String, usize> = textures
+            .iter()
+            .enumerate()
+            .map(|(index, texture)| (texture.name.clone(), index))
+            .collect();
+        KenneySpriteSheetAsset {
+            textures,
+            sheet: Handle::default(),
+            texture_atlas_layout: Handle::default(),
+            indices,
+            scale: 1.,
+        }
+    }
+
+    #[test]
+    fn layout_mirrors_wings_and_guns() {
+        let design = ShipDesign::default();
+        let sheet = sheet_for(
+            &design,
+            [
+                (PartKind::Cockpit, UVec2::new(50, 60)),
+                (PartKind::Wing, UVec2::new(40, 30)),
+                (PartKind::Engine, UVec2::new(20, 20)),
+                (PartKind::Gun, UVec2::new(10, 20)),
+            ],
+        );
+        let layout = design.layout(&sheet);
+        assert_eq!(layout.len(), 6);
+
+        for kind in [PartKind::Wing, PartKind::Gun] {
+            let pair: Vec<&PartPlacement> = layout
+                .iter()
+                .filter(|placement| placement.kind == kind)
+                .collect();
+            assert_eq!(pair.len(), 2);
+            assert_eq!(pair[0].offset.x, -pair[1].offset.x);
+            assert_eq!(pair[0].offset.y, pair[1].offset.y);
+            // the part on the left is flipped
+            for placement in pair {
+                assert_eq!(placement.flip, placement.offset.x < 0.);
+            }
+        }
+        let engine = layout
+            .iter()
+            .find(|placement| placement.kind == PartKind::Engine)
+            .unwrap();
+        assert_eq!(engine.offset.x, 0.);
+        assert!(engine.offset.y < 0.);
+    }
+
+    #[test]
+    fn stats_sum_the_parts() {
+        let design = ShipDesign::default();
+        let stats = design.stats();
+        assert_eq!(stats.loadout.lasers, 1);
+        assert_eq!(
+            stats.loadout.cooldown,
+            Duration::from_millis(BASE_COOLDOWN_MILLIS)
+        );
+        assert!(
+            (MIN_MOVEMENT_SPEED..=MAX_MOVEMENT_SPEED)
+                .contains(&stats.speed.movement_speed)
+        );
+    }
+
+    #[test]
+    fn big_guns_add_lasers_and_fire_faster() {
+        let small = ShipDesign {
+            gun: 4,
+            ..default()
+        };
+        let big = ShipDesign {
+            gun: 5,
+            ..default()
+        };
+        assert_eq!(small.stats().loadout.lasers, 1);
+        // the nose laser plus one on each wing
+        assert_eq!(big.stats().loadout.lasers, 3);
+        assert!(big.stats().loadout.cooldown < small.stats().loadout.cooldown);
+    }
+
+    #[test]
+    fn bigger_engines_are_faster() {
+        let stock = ShipDesign::default().stats();
+        let boosted = ShipDesign {
+            engine: 4,
+            ..default()
+        }
+        .stats();
+        assert!(boosted.speed.movement_speed > stock.speed.movement_speed);
+    }
+
+    #[test]
+    fn designs_must_use_existing_parts() {
+        assert!(ShipDesign::default().is_valid());
+        let mut design = ShipDesign::default();
+        design.cycle(PartKind::Gun, -1);
+        assert_eq!(design.gun, PartKind::Gun.variants() - 1);
+        assert!(design.is_valid());
+        design.engine = PartKind::Engine.variants();
+        assert!(!design.is_valid());
+    }
+}
@@ -1,13 +1,32 @@
-use bevy::prelude::*;
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
 use bevy_xpbd_2d::prelude::*;
-use bevy_hanabi::prelude::*;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    movement::MovementWrapper,
-    controller::MovementFactor,
-    utils::{asset_loader::ImageAssets, kenney_asset::KenneySpriteSheetAsset, pause_system::Pausable},
-    entities::lives::{Lives, RemoveLifeEvent},
+    animation::{AnimationMode, SpriteAnimation},
+    effects::{EffectKind, Effects},
+    movement::{LinearMovement, PlayArea, Rotate2D},
+    utils::{
+        asset_loader::{ImageAssets, SpriteSheets},
+        kenney_asset::KenneySpriteSheetAsset,
+        level_asset::CurrentLevel,
+        pause_system::Pausable,
+        screen_shake::ScreenShake,
+    },
+    entities::{
+        boss::BossPart,
+        enemies::{Enemy, EnemyLaser},
+        health::{announce_deaths, Died, Health, HitGrace, Invulnerable},
+        lives::{Lives, RemoveLifeEvent},
+        meteor::Meteor,
+        ship_builder::{spawn_selected_ship, ShipDesign},
+    },
+    gameui::settings::GameSettings,
     GameState,
+    InGame,
     Player
 }; 
 
@@ -15,7 +34,29 @@ pub struct ShipPlugin;
 
 impl Plugin for ShipPlugin {
     fn build(&self, app: &mut App) {
-        app
+        app.init_resource::<ShipLevels>()
+            .add_systems(
+                PostUpdate,
+                destroy_player_ship
+                    .after(announce_deaths)
+                    .run_if(resource_equals(
+                        Pausable::NotPaused,
+                    ))
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                render_ship_damage
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (fade_debris, despawn_stray_engine_fire)
+                    .run_if(resource_equals(
+                        Pausable::NotPaused,
+                    ))
+                    .run_if(in_state(GameState::Playing)),
+            )
             .add_systems(
                 PostUpdate,
                 player_ship_destroyed_event_handler
@@ -26,10 +67,28 @@ impl Plugin for ShipPlugin {
             )
             .add_systems(
                 PostUpdate,
-                spawn_ship_after_ship_destroyed
+                (
+                    start_respawn_countdown,
+                    tick_respawn_countdown,
+                    respawn_ship,
+                )
+                    .chain()
+                    .run_if(resource_equals(
+                        Pausable::NotPaused,
+                    ))
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                render_respawn_notice
                     .run_if(in_state(GameState::Playing)),
             )
-            .add_event::<ShipDestroyed>();
+            .add_systems(
+                OnExit(GameState::Playing),
+                cancel_respawn,
+            )
+            .add_event::<ShipDestroyed>()
+            .add_event::<ShipRespawned>();
     }
 }
 
@@ -43,17 +102,116 @@ pub struct ShipBundle {
     pub texture_atlas: TextureAtlas,
     pub player: Player,
     pub ship_type: ShipLevels,
+    pub ship_color: ShipColor,
+    pub stats: ShipStats,
+    pub health: Health,
+    pub hit_grace: HitGrace,
     pub collider: Collider,
+    pub body: ShipBodyBundle,
+    pub in_game: InGame,
     //pub wrapping_movement: MovementWrapper,
 }
 
-#[derive(Resource, Component, Clone)]
+/// The ship is a dynamic body pushed along by thrust, see
+/// `player_movement_system`, so meteors knock it about.
+/// Its mass comes from the hull's collider.
+#[derive(Bundle)]
+pub struct ShipBodyBundle {
+    pub rigid_body: RigidBody,
+    pub velocity: LinearVelocity,
+    pub spin: AngularVelocity,
+    pub thrust: ExternalForce,
+    pub damping: LinearDamping,
+    pub restitution: Restitution,
+}
+
+impl Default for ShipBodyBundle {
+    fn default() -> ShipBodyBundle {
+        ShipBodyBundle {
+            rigid_body: RigidBody::Dynamic,
+            velocity: LinearVelocity::ZERO,
+            spin: AngularVelocity::ZERO,
+            thrust: ExternalForce::default(),
+            damping: LinearDamping::default(),
+            restitution: Restitution::new(SHIP_RESTITUTION),
+        }
+    }
+}
+
+const SHIP_RESTITUTION: f32 = 0.5;
+
+/// As a resource, the level the player's ship respawns
+/// at; upgrades carry over lost lives
+#[derive(
+    Resource,
+    Component,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+)]
 pub enum ShipLevels {
+    #[default]
     Initial,
     Mid,
     Best,
 }
 
+/// Paint job of the player's hull, every hull comes in
+/// each of these
+#[derive(
+    Component,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+)]
+pub enum ShipColor {
+    #[default]
+    Blue,
+    Green,
+    Orange,
+    Red,
+}
+
+impl ShipColor {
+    pub fn all_colors() -> [ShipColor; 4] {
+        [
+            ShipColor::Blue,
+            ShipColor::Green,
+            ShipColor::Orange,
+            ShipColor::Red,
+        ]
+    }
+    /// How the color is spelled in sprite names
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShipColor::Blue => "blue",
+            ShipColor::Green => "green",
+            ShipColor::Orange => "orange",
+            ShipColor::Red => "red",
+        }
+    }
+}
+
+/// How a ship level fires, lasers are spread side by side
+#[derive(Clone, Debug)]
+pub struct WeaponLoadout {
+    pub cooldown: Duration,
+    pub lasers: u32,
+}
+
+#[derive(Clone, Debug)]
 pub struct BaseShipSpeed {
     pub movement_speed: f32, // linear speed in meters per second
     pub rotation_speed: f32, // angular speed in radians per second
@@ -61,19 +219,23 @@ pub struct BaseShipSpeed {
 
 
 impl ShipLevels {
-    pub fn base_atlas_index(&self) -> usize {
+    /// The hull number used in the sheet's sprite names
+    fn hull(&self) -> u32 {
         match &self {
-            ShipLevels::Initial => 200,
-            ShipLevels::Mid => 207,
-            ShipLevels::Best => 214,
+            ShipLevels::Initial => 1,
+            ShipLevels::Mid => 2,
+            ShipLevels::Best => 3,
         }
     }
-    pub fn life_atlas_index(&self) -> usize {
-        match &self {
-            ShipLevels::Initial => 188,
-            ShipLevels::Mid => 192,
-            ShipLevels::Best => 196,
-        }
+    pub fn base_sprite_name(&self, color: ShipColor) -> String {
+        format!("playerShip{}_{}.png", self.hull(), color.name())
+    }
+    /// Cracks drawn over the hull, `stage` 1 to 3
+    pub fn damage_sprite_name(&self, stage: u32) -> String {
+        format!("playerShip{}_damage{stage}.png", self.hull())
+    }
+    pub fn life_sprite_name(&self, color: ShipColor) -> String {
+        format!("playerLife{}_{}.png", self.hull(), color.name())
     }
     pub fn all_ships() -> Vec<ShipLevels> {
         vec![
@@ -82,8 +244,58 @@ impl ShipLevels {
             ShipLevels::Best,
         ]
     }
+    /// The level an upgrade leads to, if any
+    pub fn next(&self) -> Option<ShipLevels> {
+        match self {
+            ShipLevels::Initial => Some(ShipLevels::Mid),
+            ShipLevels::Mid => Some(ShipLevels::Best),
+            ShipLevels::Best => None,
+        }
+    }
+    /// A capsule across the wings, the hulls are wider
+    /// than they are long
     pub fn collider(&self) -> Collider {
-        Collider::capsule(40., 10.)
+        let half_span = match self {
+            ShipLevels::Initial => 18.,
+            ShipLevels::Mid => 24.,
+            ShipLevels::Best => 20.,
+        };
+        Collider::capsule_endpoints(
+            Vec2::new(-half_span, 0.),
+            Vec2::new(half_span, 0.),
+            30.,
+        )
+    }
+    /// Hits the hull takes before breaking up, better
+    /// hulls are tougher
+    pub fn max_health(&self) -> f32 {
+        match self {
+            ShipLevels::Initial => 3.,
+            ShipLevels::Mid => 4.,
+            ShipLevels::Best => 5.,
+        }
+    }
+    pub fn weapon_loadout(&self) -> WeaponLoadout {
+        match self {
+            ShipLevels::Initial => WeaponLoadout {
+                cooldown: Duration::from_millis(200),
+                lasers: 1,
+            },
+            ShipLevels::Mid => WeaponLoadout {
+                cooldown: Duration::from_millis(180),
+                lasers: 2,
+            },
+            ShipLevels::Best => WeaponLoadout {
+                cooldown: Duration::from_millis(150),
+                lasers: 3,
+            },
+        }
+    }
+    pub fn stats(&self) -> ShipStats {
+        ShipStats {
+            speed: self.base_ship_speed(),
+            loadout: self.weapon_loadout(),
+        }
     }
     pub fn base_ship_speed(&self) -> BaseShipSpeed {
         match self {
@@ -103,79 +315,522 @@ impl ShipLevels {
     }
 }
 
+/// What movement and weapons read off the player's ship,
+/// taken from its [`ShipLevels`] or summed up from the
+/// parts of a designed ship
+#[derive(Component, Clone, Debug)]
+pub struct ShipStats {
+    pub speed: BaseShipSpeed,
+    pub loadout: WeaponLoadout,
+}
+
 #[derive(Event)]
 pub struct ShipDestroyed {
     pub destroyed_at: Transform,
 }
 
-fn player_ship_destroyed_event_handler(
+/// After a hit the ship shrugs off further damage for
+/// this long
+pub const SHIP_HIT_GRACE: Duration = Duration::from_millis(1000);
+
+/// Breaks the ship into debris and removes it along with
+/// its engine fire
+fn destroy_player_ship(
     mut commands: Commands,
     images: Res<ImageAssets>,
-    mut events: EventReader<ShipDestroyed>,
     sheets: Res<Assets<KenneySpriteSheetAsset>>,
-    mut effect: Query<(
-        &mut EffectProperties,
-        &mut EffectSpawner,
-        &mut Transform,
-    )>,
-    mut ship_movement: ResMut<MovementFactor>,
-    mut life_events: EventWriter<RemoveLifeEvent>,
+    mut events: EventReader<Died>,
+    mut ship_destroyed: EventWriter<ShipDestroyed>,
+    player: Query<
+        (&Transform, &ShipLevels, Option<&ShipDesign>),
+        With<Player>,
+    >,
+) {
+    for Died { entity } in events.read() {
+        let Ok((transform, level, design)) = player.get(*entity)
+        else {
+            continue;
+        };
+        commands.entity(*entity).despawn_recursive();
+        ship_destroyed.send(ShipDestroyed {
+            destroyed_at: *transform,
+        });
+
+        let Some(space_sheet) = sheets.get(&images.space_sheet)
+        else {
+            continue;
+        };
+        let pieces: Vec<(String, Vec3, bool)> = match design {
+            Some(design) => design
+                .layout(space_sheet)
+                .into_iter()
+                .map(|placement| {
+                    (
+                        design.sprite_name(placement.kind),
+                        placement.offset,
+                        placement.flip,
+                    )
+                })
+                .collect(),
+            // stock hulls have no parts, scatter cracked
+            // plating and scrap instead
+            None => [
+                (level.damage_sprite_name(3), Vec3::ZERO),
+                ("engine1.png".to_string(), Vec3::new(0., -30., 0.)),
+                ("scratch1.png".to_string(), Vec3::new(-25., 0., 0.)),
+                ("scratch2.png".to_string(), Vec3::new(25., 0., 0.)),
+                ("scratch3.png".to_string(), Vec3::new(0., 25., 0.)),
+            ]
+            .into_iter()
+            .map(|(name, offset)| (name, offset, false))
+            .collect(),
+        };
+        spawn_debris(&mut commands, space_sheet, transform, pieces);
+    }
+}
+
+/// A drifting piece of a destroyed ship, fading out
+#[derive(Component)]
+struct Debris(Timer);
+
+const DEBRIS_LIFETIME: Duration = Duration::from_millis(2500);
+/// Pixels per frame, like [`LinearMovement`]
+const DEBRIS_MAX_DRIFT: f32 = 3.;
+const DEBRIS_MAX_SPIN: f32 = 4.;
+
+fn spawn_debris(
+    commands: &mut Commands,
+    space_sheet: &KenneySpriteSheetAsset,
+    ship: &Transform,
+    pieces: Vec<(String, Vec3, bool)>,
+) {
+    let mut rng = rand::thread_rng();
+    for (sprite_name, offset, flip) in pieces {
+        let Ok(atlas) = space_sheet.atlas(&sprite_name) else {
+            continue;
+        };
+        let offset = ship.rotation * offset;
+        // pieces fly away from the middle, the core piece
+        // in a random direction
+        let outwards = offset
+            .xy()
+            .try_normalize()
+            .unwrap_or_else(|| {
+                Vec2::from_angle(rng.gen_range(0.0..TAU))
+            });
+        commands.spawn((
+            SpriteBundle {
+                transform: ship
+                    .with_translation(ship.translation + offset),
+                texture: space_sheet.sheet.clone(),
+                sprite: Sprite {
+                    flip_x: flip,
+                    ..default()
+                },
+                ..default()
+            },
+            atlas,
+            LinearMovement {
+                movement_factor: outwards
+                    * rng.gen_range(1.0..DEBRIS_MAX_DRIFT),
+                movement_direction: Quat::IDENTITY,
+            },
+            Rotate2D {
+                speed: rng.gen_range(-DEBRIS_MAX_SPIN..DEBRIS_MAX_SPIN),
+                axis: Vec3::Z,
+            },
+            Debris(Timer::new(DEBRIS_LIFETIME, TimerMode::Once)),
+            InGame,
+        ));
+    }
+}
+
+fn fade_debris(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut debris: Query<(Entity, &mut Debris, &mut Sprite)>,
+) {
+    for (entity, mut debris, mut sprite) in &mut debris {
+        if debris.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        sprite.color.set_a(debris.0.fraction_remaining());
+    }
+}
+
+/// Engine fire only exists on a ship, any left without
+/// one is removed
+fn despawn_stray_engine_fire(
+    mut commands: Commands,
+    fires: Query<Entity, (With<EngineFire>, Without<Parent>)>,
+) {
+    for entity in &fires {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Cracks over a stock hull, showing how hurt it is
+#[derive(Component)]
+struct DamageOverlay;
+
+/// Stock hulls whose cracks need redrawing, the part
+/// sheets have no cracked sprites
+type DamagedStockHull = (
+    With<Player>,
+    Without<ShipDesign>,
+    Or<(Changed<Health>, Changed<ShipLevels>)>,
+);
+
+fn render_ship_damage(
+    mut commands: Commands,
+    images: Res<ImageAssets>,
+    sheets: Res<Assets<KenneySpriteSheetAsset>>,
+    ships: Query<
+        (Entity, &Health, &ShipLevels, Option<&Children>),
+        DamagedStockHull,
+    >,
+    overlays: Query<(), With<DamageOverlay>>,
 ) {
     let Some(space_sheet) = sheets.get(&images.space_sheet)
     else {
-        warn!("player_ship_destroyed_event_handler requires meteor sprites to be loaded");
         return;
     };
+    for (entity, health, level, children) in &ships {
+        for child in children.into_iter().flatten() {
+            if overlays.contains(*child) {
+                commands.entity(*child).despawn_recursive();
+            }
+        }
+        let stage = ((1. - health.fraction()) * 3.).ceil() as u32;
+        if health.is_dead() || stage == 0 {
+            continue;
+        }
+        let overlay = commands
+            .spawn((
+                SpriteBundle {
+                    transform: Transform::from_xyz(0., 0., 0.5),
+                    texture: space_sheet.sheet.clone(),
+                    ..default()
+                },
+                space_sheet
+                    .atlas(&level.damage_sprite_name(stage.min(3)))
+                    .expect("damage sprite to exist"),
+                DamageOverlay,
+            ))
+            .id();
+        commands.entity(entity).add_child(overlay);
+    }
+}
 
-    let Ok((
-        mut properties,
-        mut spawner,
-        mut effect_transform,
-    )) = effect.get_single_mut()
-    else {
-        warn!("effect not ready yet, returning");
-        return;
-    };
+/// How much a lost ship shakes the screen, 1 is the most
+const SHIP_DESTROYED_TRAUMA: f32 = 0.8;
+const SHIP_EXPLOSION_COLOR: Color = Color::rgb(1., 0.6, 0.2);
 
+fn player_ship_destroyed_event_handler(
+    mut events: EventReader<ShipDestroyed>,
+    mut effects: Effects,
+    mut screen_shake: ResMut<ScreenShake>,
+    mut life_events: EventWriter<RemoveLifeEvent>,
+) {
     for ShipDestroyed {
         destroyed_at,
     } in &mut events.read()
     {
-        effect_transform.translation =
-            destroyed_at.translation;
+        life_events.send(RemoveLifeEvent);
+        screen_shake.add_trauma(SHIP_DESTROYED_TRAUMA);
+        effects.play(
+            EffectKind::ShipExplosion,
+            destroyed_at.translation,
+            SHIP_EXPLOSION_COLOR,
+        );
+    }
+}
 
-        let color = Color::lch(
-            1.,
-            1.,
-            rand::random::<f32>() * 360.,
-        )
-        .as_linear_rgba_u32();
-        properties.set("spawn_color", color.into());
+/// Time between losing a ship and the next one warping in
+const RESPAWN_DELAY: Duration = Duration::from_millis(2000);
+/// A fresh ship blinks and cannot be hurt for this long
+const RESPAWN_INVULNERABILITY: Duration =
+    Duration::from_millis(3000);
+/// How far a fresh ship keeps from meteors and enemies
+const RESPAWN_CLEARANCE: f32 = 150.;
+const RESPAWN_ATTEMPTS: usize = 32;
+const WARP_COLOR: Color = Color::rgb(0.5, 0.8, 1.);
+
+/// Counts down to the next ship while the player waits
+/// to respawn
+#[derive(Resource)]
+pub struct RespawnCountdown(pub Timer);
 
-        // Spawn the particles
-        spawner.reset();
+impl Default for RespawnCountdown {
+    fn default() -> Self {
+        Self(Timer::new(RESPAWN_DELAY, TimerMode::Once))
+    }
+}
 
-        ship_movement.0 = Vec2::ZERO;
+/// A new ship warped in after the last one was lost
+#[derive(Event)]
+pub struct ShipRespawned {
+    pub at: Transform,
+}
 
-        life_events.send(RemoveLifeEvent);
+fn start_respawn_countdown(
+    mut commands: Commands,
+    mut events: EventReader<ShipDestroyed>,
+) {
+    if events.read().last().is_none() {
+        return;
+    }
+    commands.insert_resource(RespawnCountdown::default());
+}
+
+fn cancel_respawn(mut commands: Commands) {
+    commands.remove_resource::<RespawnCountdown>();
+}
+
+/// The level's start if it is clear, otherwise the
+/// clearest of a few random points in the play area
+fn safe_spawn_point(
+    rng: &mut impl Rng,
+    preferred: Vec2,
+    half_size: Vec2,
+    obstacles: &[Vec2],
+) -> Vec2 {
+    let clearance = |point: Vec2| {
+        obstacles
+            .iter()
+            .map(|obstacle| obstacle.distance(point))
+            .fold(f32::INFINITY, f32::min)
+    };
+    let mut best = (preferred, clearance(preferred));
+    for _ in 0..RESPAWN_ATTEMPTS {
+        if best.1 >= RESPAWN_CLEARANCE {
+            break;
+        }
+        let candidate = Vec2::new(
+            rng.gen_range(-half_size.x..half_size.x),
+            rng.gen_range(-half_size.y..half_size.y),
+        );
+        let candidate_clearance = clearance(candidate);
+        if candidate_clearance > best.1 {
+            best = (candidate, candidate_clearance);
+        }
+    }
+    best.0
+}
+
+fn tick_respawn_countdown(
+    time: Res<Time>,
+    countdown: Option<ResMut<RespawnCountdown>>,
+) {
+    if let Some(mut countdown) = countdown {
+        countdown.0.tick(time.delta());
+    }
+}
+
+/// Anything a fresh ship should not warp in on top of
+type RespawnObstacles = Or<(
+    With<Meteor>,
+    With<Enemy>,
+    With<EnemyLaser>,
+    With<BossPart>,
+)>;
+
+/// Picks where a fresh ship warps in: the level's start,
+/// or somewhere clear of [`RespawnObstacles`]
+#[derive(SystemParam)]
+struct RespawnSite<'w, 's> {
+    level: CurrentLevel<'w>,
+    area: PlayArea<'w, 's>,
+    obstacles: Query<'w, 's, &'static GlobalTransform, RespawnObstacles>,
+}
+
+impl RespawnSite<'_, '_> {
+    fn pick(&self, rng: &mut impl Rng) -> Transform {
+        let start = self
+            .level
+            .get()
+            .map(|level| level.player_start.transform())
+            .unwrap_or_default();
+        let obstacles: Vec<Vec2> = self
+            .obstacles
+            .iter()
+            .map(|transform| transform.translation().xy())
+            .collect();
+        let position = safe_spawn_point(
+            rng,
+            start.translation.xy(),
+            self.area.half_size(),
+            &obstacles,
+        );
+        start.with_translation(position.extend(start.translation.z))
+    }
+}
+
+/// Builds the ship picked in the menus, at the level the
+/// run has reached
+#[derive(SystemParam)]
+struct SelectedShip<'w> {
+    sheets: SpriteSheets<'w>,
+    settings: Res<'w, GameSettings>,
+    ship_level: Res<'w, ShipLevels>,
+}
+
+impl SelectedShip<'_> {
+    fn spawn(
+        &self,
+        commands: &mut Commands,
+        transform: Transform,
+    ) -> Option<Entity> {
+        let space_sheet = self.sheets.space()?;
+        Some(spawn_selected_ship(
+            commands,
+            space_sheet,
+            &self.settings,
+            *self.ship_level,
+            transform,
+        ))
     }
 }
 
-fn spawn_ship_after_ship_destroyed(
+fn respawn_ship(
     mut commands: Commands,
-    images: Res<ImageAssets>,
-    sheets: Res<Assets<KenneySpriteSheetAsset>>,
+    countdown: Option<Res<RespawnCountdown>>,
     lives: Res<Lives>,
+    player: Query<(), With<Player>>,
+    selected_ship: SelectedShip,
+    site: RespawnSite,
+    mut respawned: EventWriter<ShipRespawned>,
 ) {
-    if !lives.is_changed() || lives.0 == 0 || lives.0 == 3 {
+    if !countdown.is_some_and(|countdown| countdown.0.finished()) {
         return;
     }
-    let Some(space_sheet) = sheets.get(&images.space_sheet)
+    commands.remove_resource::<RespawnCountdown>();
+    // out of lives, or a ship is already flying
+    if lives.0 == 0 || !player.is_empty() {
+        return;
+    }
+
+    let transform = site.pick(&mut rand::thread_rng());
+    let Some(ship) = selected_ship.spawn(&mut commands, transform)
     else {
-        warn!("player_ship_destroyed_event_handler requires meteor sprites to be loaded");
+        warn!("respawn_ship requires ship sprites to be loaded");
         return;
     };
+    commands.entity(ship).insert(Invulnerable(Timer::new(
+        RESPAWN_INVULNERABILITY,
+        TimerMode::Once,
+    )));
+    respawned.send(ShipRespawned { at: transform });
+}
+
+#[derive(Component)]
+struct RespawnNotice;
+
+fn render_respawn_notice(
+    mut commands: Commands,
+    countdown: Option<Res<RespawnCountdown>>,
+    lives: Res<Lives>,
+    mut respawned: EventReader<ShipRespawned>,
+    mut effects: Effects,
+    mut notices: Query<(Entity, &mut Text), With<RespawnNotice>>,
+) {
+    let mut arrived = false;
+    for ShipRespawned { at } in respawned.read() {
+        info!("Ship respawned at {}", at.translation.xy());
+        effects.play(EffectKind::Warp, at.translation, WARP_COLOR);
+        arrived = true;
+    }
+    let Some(countdown) =
+        countdown.filter(|_| lives.0 > 0 && !arrived)
+    else {
+        for (entity, _) in &notices {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    };
+    let message = format!(
+        "Next ship in {:.0}",
+        countdown.0.remaining_secs().ceil()
+    );
+    if let Ok((_, mut text)) = notices.get_single_mut() {
+        text.sections[0].value = message;
+        return;
+    }
+    commands.spawn((
+        TextBundle::from_section(
+            message,
+            TextStyle {
+                font_size: 40.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            top: Val::Percent(60.),
+            ..default()
+        })
+        .with_text_justify(JustifyText::Center),
+        RespawnNotice,
+        InGame,
+    ));
+}
 
+const ENGINE_FIRE_FPS: f32 = 24.;
+const THRUSTER_FIRE_FPS: f32 = 16.;
+const SHIELD_SHIMMER_FPS: f32 = 8.;
+const SHIELD_SHIMMER_CYCLES: u32 = 3;
+
+/// Spawns the player ship with its engine fire children
+/// and a shield shimmer that plays as it warps in
+pub fn spawn_player_ship(
+    commands: &mut Commands,
+    space_sheet: &KenneySpriteSheetAsset,
+    ship_type: ShipLevels,
+    ship_color: ShipColor,
+    transform: Transform,
+) -> Entity {
+    let engine_animation = SpriteAnimation::from_prefix(
+        space_sheet,
+        "fire",
+        ENGINE_FIRE_FPS,
+        AnimationMode::Loop,
+    )
+    .expect("engine fire frames to exist");
+    let thruster_animation = SpriteAnimation::from_prefix(
+        space_sheet,
+        "fire",
+        THRUSTER_FIRE_FPS,
+        AnimationMode::PingPong { cycles: None },
+    )
+    .expect("thruster fire frames to exist");
+    let shield_animation = SpriteAnimation::from_prefix(
+        space_sheet,
+        "shield",
+        SHIELD_SHIMMER_FPS,
+        AnimationMode::PingPong {
+            cycles: Some(SHIELD_SHIMMER_CYCLES),
+        },
+    )
+    .expect("shield frames to exist")
+    .despawn_on_finish();
+    let shield = commands
+    .spawn((
+        SpriteBundle {
+            transform: Transform::from_xyz(0., 0., 2.),
+            texture: space_sheet.sheet.clone(),
+            sprite: Sprite {
+                color: Color::rgba(1., 1., 1., 0.6),
+                ..default()
+            },
+            ..default()
+        },
+        shield_animation.atlas(space_sheet),
+        shield_animation,
+    ))
+    .id();
     let engine_fire = commands
     .spawn((
         SpriteBundle {
@@ -190,12 +845,8 @@ fn spawn_ship_after_ship_destroyed(
             visibility: Visibility::Hidden,
             ..default()
         },
-        TextureAtlas {
-            index: 74,
-            layout: space_sheet
-                .texture_atlas_layout
-                .clone(),
-        },
+        engine_animation.atlas(space_sheet),
+        engine_animation.clone(),
         EngineFire,
     ))
     .id();
@@ -213,12 +864,8 @@ fn spawn_ship_after_ship_destroyed(
             visibility: Visibility::Hidden,
             ..default()
         },
-        TextureAtlas {
-            index: 75,
-            layout: space_sheet
-                .texture_atlas_layout
-                .clone(),
-        },
+        thruster_animation.atlas(space_sheet),
+        thruster_animation.clone(),
         EngineFire,
     ))
     .id();
@@ -236,33 +883,75 @@ fn spawn_ship_after_ship_destroyed(
             visibility: Visibility::Hidden,
             ..default()
         },
-        TextureAtlas {
-            index: 75,
-            layout: space_sheet
-                .texture_atlas_layout
-                .clone(),
-        },
+        thruster_animation.atlas(space_sheet),
+        thruster_animation.clone(),
         EngineFire,
     ))
     .id();
     commands.spawn(ShipBundle {
         sprite_bundle: SpriteBundle {
+            transform,
             texture: space_sheet.sheet.clone(),
             ..default()
         },
-        texture_atlas: TextureAtlas {
-            index: 200,
-            layout: space_sheet
-                    .texture_atlas_layout
-                    .clone(),
-        },
+        texture_atlas: space_sheet
+            .atlas(&ship_type.base_sprite_name(ship_color))
+            .expect("ship sprite to exist"),
         player: Player,
-        ship_type: ShipLevels::Initial,
-        collider: Collider::circle(32.),
+        ship_type,
+        ship_color,
+        stats: ship_type.stats(),
+        health: Health::new(ship_type.max_health()),
+        hit_grace: HitGrace(SHIP_HIT_GRACE),
+        collider: ship_type.collider(),
+        body: ShipBodyBundle::default(),
+        in_game: InGame,
         //wrapping_movement: MovementWrapper
     })
     .add_child(engine_fire)
     .add_child(right_truster)
-    .add_child(left_truster);
+    .add_child(left_truster)
+    .add_child(shield)
+    .id()
+}
+
 
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    const HALF_SIZE: Vec2 = Vec2::new(640., 360.);
+
+    #[test]
+    fn a_clear_start_is_kept() {
+        let mut rng = StdRng::seed_from_u64(21);
+        let start = Vec2::new(10., 20.);
+        let far = start + Vec2::X * (RESPAWN_CLEARANCE + 1.);
+        assert_eq!(
+            safe_spawn_point(&mut rng, start, HALF_SIZE, &[far]),
+            start
+        );
+        assert_eq!(safe_spawn_point(&mut rng, start, HALF_SIZE, &[]), start);
+    }
+
+    #[test]
+    fn a_blocked_start_moves_somewhere_clearer() {
+        let mut rng = StdRng::seed_from_u64(21);
+        let start = Vec2::ZERO;
+        let obstacles = [Vec2::new(5., 0.), Vec2::new(-5., 10.)];
+        let point =
+            safe_spawn_point(&mut rng, start, HALF_SIZE, &obstacles);
+        assert_ne!(point, start);
+        assert!(point.x.abs() <= HALF_SIZE.x);
+        assert!(point.y.abs() <= HALF_SIZE.y);
+        let clearance = |point: Vec2| {
+            obstacles
+                .iter()
+                .map(|obstacle| obstacle.distance(point))
+                .fold(f32::INFINITY, f32::min)
+        };
+        assert!(clearance(point) > clearance(start));
+    }
 }
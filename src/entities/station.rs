@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    entities::{enemies::EnemyDestroyed, meteor::MeteorDestroyed},
+    movement::Rotate2D,
+    utils::{
+        asset_loader::ImageAssets,
+        kenney_asset::KenneySpriteSheetAsset,
+        pause_system::Pausable,
+    },
+    GameState, InGame,
+};
+
+pub struct StationPlugin;
+
+impl Plugin for StationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            spawn_impact_flashes
+                .run_if(resource_equals(Pausable::NotPaused))
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            fade_impact_flashes
+                .run_if(resource_equals(Pausable::NotPaused))
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// A space station placed by the level, scenery from the
+/// space shooter sheet that the play area drifts past
+#[derive(Component)]
+pub struct Station;
+
+#[derive(Bundle)]
+pub struct StationBundle {
+    station: Station,
+    sprite_bundle: SpriteBundle,
+    texture_atlas: TextureAtlas,
+    spin: Rotate2D,
+    in_game: InGame,
+}
+
+const STATION_SPIN_SPEED: f32 = 0.02;
+
+impl StationBundle {
+    /// `None` if the station sheet has no `sprite_name`
+    pub fn new(
+        sprite_name: &str,
+        transform: Transform,
+        station_sheet: &KenneySpriteSheetAsset,
+    ) -> Option<StationBundle> {
+        Some(StationBundle {
+            station: Station,
+            sprite_bundle: SpriteBundle {
+                transform: transform
+                    .with_scale(Vec3::splat(station_sheet.scale)),
+                texture: station_sheet.sheet.clone(),
+                ..default()
+            },
+            texture_atlas: station_sheet.atlas(sprite_name).ok()?,
+            spin: Rotate2D {
+                speed: STATION_SPIN_SPEED,
+                axis: Vec3::Z,
+            },
+            in_game: InGame,
+        })
+    }
+}
+
+/// A short-lived flash left where a meteor or enemy was
+/// shot
+#[derive(Component)]
+struct ImpactFlash(Timer);
+
+const IMPACT_FLASH_SPRITE: &str = "spaceEffects_016.png";
+const IMPACT_FLASH_DURATION: Duration =
+    Duration::from_millis(250);
+
+fn spawn_impact_flashes(
+    mut commands: Commands,
+    images: Res<ImageAssets>,
+    sheets: Res<Assets<KenneySpriteSheetAsset>>,
+    mut meteors: EventReader<MeteorDestroyed>,
+    mut enemies: EventReader<EnemyDestroyed>,
+) {
+    let Some(station_sheet) =
+        sheets.get(&images.station_sheet)
+    else {
+        warn!("spawn_impact_flashes requires effect sprites to be loaded");
+        return;
+    };
+    let Ok(atlas) = station_sheet.atlas(IMPACT_FLASH_SPRITE) else {
+        warn!(
+            "Skipping impact flashes, {IMPACT_FLASH_SPRITE} is not on the station sheet"
+        );
+        return;
+    };
+
+    let destroyed_at = meteors
+        .read()
+        .map(|meteor| meteor.destroyed_at)
+        .chain(enemies.read().map(|enemy| enemy.destroyed_at));
+    for destroyed_at in destroyed_at {
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_translation(
+                    destroyed_at.translation.xy().extend(2.),
+                )
+                .with_scale(Vec3::splat(station_sheet.scale)),
+                texture: station_sheet.sheet.clone(),
+                ..default()
+            },
+            atlas.clone(),
+            ImpactFlash(Timer::new(
+                IMPACT_FLASH_DURATION,
+                TimerMode::Once,
+            )),
+            InGame,
+        ));
+    }
+}
+
+fn fade_impact_flashes(
+    mut commands: Commands,
+    time: Res<Time>,
+    images: Res<ImageAssets>,
+    sheets: Res<Assets<KenneySpriteSheetAsset>>,
+    mut flashes: Query<(
+        Entity,
+        &mut ImpactFlash,
+        &mut Sprite,
+        &mut Transform,
+    )>,
+) {
+    let scale = sheets
+        .get(&images.station_sheet)
+        .map_or(1., |sheet| sheet.scale);
+    for (entity, mut flash, mut sprite, mut transform) in
+        &mut flashes
+    {
+        flash.0.tick(time.delta());
+        if flash.0.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        sprite.color.set_a(flash.0.fraction_remaining());
+        transform.scale = Vec3::splat(
+            scale * (1. + flash.0.fraction()),
+        );
+    }
+}
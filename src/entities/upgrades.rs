@@ -0,0 +1,308 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::{
+    entities::{
+        health::Health,
+        ore::Cargo,
+        score::Score,
+        spaceship::{ShipColor, ShipLevels},
+    },
+    utils::{
+        asset_loader::{FontAssets, ImageAssets},
+        kenney_asset::KenneySpriteSheetAsset,
+        pause_system::Pausable,
+        save_system::{read_unlocked_hull, write_unlocked_hull},
+    },
+    gameui::settings::GameSettings,
+    GameState, InGame, Player,
+};
+
+pub struct UpgradePlugin;
+
+impl Plugin for UpgradePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ShipUpgraded>()
+            .add_systems(Startup, load_unlocked_hull)
+            .add_systems(
+                OnEnter(GameState::Playing),
+                spawn_upgrade_hint,
+            )
+            .add_systems(
+                OnExit(GameState::Playing),
+                store_unlocked_hull,
+            )
+            .add_systems(
+                Update,
+                (
+                    (upgrade_on_score, buy_upgrade),
+                    apply_ship_upgrade,
+                    play_upgrade_transition,
+                    render_upgrade_hint,
+                )
+                    .chain()
+                    .run_if(resource_equals(
+                        Pausable::NotPaused,
+                    ))
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// What it takes to reach a ship level: either reach
+/// `score`, or spend `cost` out of the cargo
+pub struct UpgradeRequirement {
+    pub score: u32,
+    pub cost: Cargo,
+}
+
+impl ShipLevels {
+    /// The requirement to upgrade from this level, `None`
+    /// when there is nothing left to upgrade to
+    pub fn upgrade_requirement(
+        &self,
+    ) -> Option<UpgradeRequirement> {
+        match self {
+            ShipLevels::Initial => Some(UpgradeRequirement {
+                score: 2000,
+                cost: Cargo {
+                    bronze: 10,
+                    silver: 10,
+                    gold: 0,
+                },
+            }),
+            ShipLevels::Mid => Some(UpgradeRequirement {
+                score: 6000,
+                cost: Cargo {
+                    bronze: 0,
+                    silver: 15,
+                    gold: 5,
+                },
+            }),
+            ShipLevels::Best => None,
+        }
+    }
+}
+
+/// Best hull ever upgraded to, persisted between runs.
+/// The hangar offers the hulls up to this one.
+#[derive(Debug, Resource, Default, PartialEq, Eq)]
+pub struct UnlockedHull(pub ShipLevels);
+
+fn load_unlocked_hull(mut commands: Commands) {
+    let hull = read_unlocked_hull().unwrap_or_else(|error| {
+        warn!("Could not read unlocked hulls: {error}");
+        ShipLevels::default()
+    });
+    commands.insert_resource(UnlockedHull(hull));
+}
+
+fn store_unlocked_hull(unlocked: Res<UnlockedHull>) {
+    if let Err(error) = write_unlocked_hull(unlocked.0) {
+        error!("Could not store unlocked hulls: {error}");
+    }
+}
+
+#[derive(Event)]
+pub struct ShipUpgraded {
+    pub level: ShipLevels,
+}
+
+const UPGRADE_KEY: KeyCode = KeyCode::KeyU;
+
+fn upgrade_on_score(
+    score: Res<Score>,
+    ship_level: Res<ShipLevels>,
+    settings: Res<GameSettings>,
+    mut upgraded: EventWriter<ShipUpgraded>,
+) {
+    // designed ships are not hulls and cannot upgrade
+    if !score.is_changed() || settings.design.is_some() {
+        return;
+    }
+    let (Some(requirement), Some(level)) =
+        (ship_level.upgrade_requirement(), ship_level.next())
+    else {
+        return;
+    };
+    if score.0 >= requirement.score {
+        upgraded.send(ShipUpgraded { level });
+    }
+}
+
+fn buy_upgrade(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    ship_level: Res<ShipLevels>,
+    mut cargo: ResMut<Cargo>,
+    settings: Res<GameSettings>,
+    mut upgraded: EventWriter<ShipUpgraded>,
+) {
+    if !keyboard_input.just_pressed(UPGRADE_KEY)
+        || settings.design.is_some()
+    {
+        return;
+    }
+    let (Some(requirement), Some(level)) =
+        (ship_level.upgrade_requirement(), ship_level.next())
+    else {
+        return;
+    };
+    if cargo.spend(&requirement.cost) {
+        upgraded.send(ShipUpgraded { level });
+    } else {
+        info!("Not enough ore to upgrade, have {:?}", *cargo);
+    }
+}
+
+/// Briefly grows and tints the ship after an upgrade
+#[derive(Component)]
+struct UpgradeTransition(Timer);
+
+const UPGRADE_TRANSITION_DURATION: Duration =
+    Duration::from_millis(600);
+const UPGRADE_TINT: Color = Color::rgb(0.4, 1., 1.);
+
+fn apply_ship_upgrade(
+    mut commands: Commands,
+    images: Res<ImageAssets>,
+    sheets: Res<Assets<KenneySpriteSheetAsset>>,
+    mut events: EventReader<ShipUpgraded>,
+    mut ship_level: ResMut<ShipLevels>,
+    mut unlocked: ResMut<UnlockedHull>,
+    player: Query<(Entity, &ShipColor), With<Player>>,
+) {
+    // score and ore may both upgrade in the same frame,
+    // only go up one level at a time
+    let Some(ShipUpgraded { level }) = events.read().last()
+    else {
+        return;
+    };
+    if ship_level.next() != Some(*level) {
+        return;
+    }
+    let Some(space_sheet) = sheets.get(&images.space_sheet)
+    else {
+        warn!("apply_ship_upgrade requires ship sprites to be loaded");
+        return;
+    };
+
+    info!("Upgrading ship to {level:?}");
+    *ship_level = *level;
+    unlocked.0 = unlocked.0.max(*level);
+    // a destroyed ship respawns at the new level
+    let Ok((entity, color)) = player.get_single() else {
+        return;
+    };
+    commands.entity(entity).insert((
+        *level,
+        space_sheet
+            .atlas(&level.base_sprite_name(*color))
+            .expect("ship sprite to exist"),
+        level.collider(),
+        level.stats(),
+        // the new hull comes out of the dock undamaged
+        Health::new(level.max_health()),
+        UpgradeTransition(Timer::new(
+            UPGRADE_TRANSITION_DURATION,
+            TimerMode::Once,
+        )),
+    ));
+}
+
+fn play_upgrade_transition(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut ships: Query<(
+        Entity,
+        &mut UpgradeTransition,
+        &mut Sprite,
+        &mut Transform,
+    )>,
+) {
+    for (entity, mut transition, mut sprite, mut transform) in
+        &mut ships
+    {
+        transition.0.tick(time.delta());
+        if transition.0.finished() {
+            sprite.color = Color::WHITE;
+            transform.scale = Vec3::ONE;
+            commands.entity(entity).remove::<UpgradeTransition>();
+            continue;
+        }
+        // rises and falls back over the transition
+        let pulse = (transition.0.fraction()
+            * std::f32::consts::PI)
+            .sin();
+        transform.scale = Vec3::splat(1. + 0.3 * pulse);
+        let tint = UPGRADE_TINT.rgb_to_vec3();
+        sprite.color = Color::rgb_from_array(
+            Vec3::ONE.lerp(tint, pulse).to_array(),
+        );
+    }
+}
+
+#[derive(Component)]
+struct UpgradeHint;
+
+fn spawn_upgrade_hint(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(20.),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            InGame,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: fonts.roboto.clone(),
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                    },
+                ),
+                UpgradeHint,
+            ));
+        });
+}
+
+/// Tells the player when they can afford the next ship
+fn render_upgrade_hint(
+    cargo: Res<Cargo>,
+    ship_level: Res<ShipLevels>,
+    settings: Res<GameSettings>,
+    mut hints: Query<&mut Text, With<UpgradeHint>>,
+) {
+    if !cargo.is_changed() && !ship_level.is_changed() {
+        return;
+    }
+    let hint = match ship_level.upgrade_requirement() {
+        Some(requirement)
+            if cargo.covers(&requirement.cost)
+                && settings.design.is_none() =>
+        {
+            format!(
+                "Press U to upgrade your ship ({} bronze, {} silver, {} gold)",
+                requirement.cost.bronze,
+                requirement.cost.silver,
+                requirement.cost.gold
+            )
+        }
+        _ => String::new(),
+    };
+    for mut text in &mut hints {
+        text.sections[0].value.clone_from(&hint);
+    }
+}
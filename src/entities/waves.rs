@@ -0,0 +1,551 @@
+use std::time::Duration;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use rand::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    entities::{
+        boss::{spawn_boss, Boss},
+        enemies::{spawn_enemy, Enemy, EnemyKind},
+        meteor::{Meteor, MeteorBundle, MeteorType},
+    },
+    movement::PlayArea,
+    utils::{
+        asset_loader::SpriteSheets,
+        level_asset::CurrentLevel,
+        pause_system::Pausable,
+    },
+    GameState, InGame, Player,
+};
+
+pub struct WavePlugin;
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CurrentWave>()
+            .add_event::<WaveStarted>()
+            .add_systems(
+                Update,
+                (
+                    tick_intermission,
+                    wave_director,
+                    show_wave_banner,
+                    fade_wave_banner,
+                )
+                    .chain()
+                    .run_if(resource_equals(
+                        Pausable::NotPaused,
+                    ))
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+/// Designer-tunable wave list, part of a
+/// [`LevelAsset`](crate::utils::level_asset::LevelAsset)
+#[derive(Debug, Clone, Deserialize)]
+pub struct WaveTable {
+    /// Meteors never spawn closer than this to the player
+    pub safe_radius: f32,
+    /// Pause between clearing a wave and the next one
+    pub intermission_seconds: f32,
+    pub waves: Vec<WaveDefinition>,
+    pub endless: EndlessGrowth,
+    /// Every `boss_every`th wave is a boss fight instead,
+    /// 0 turns bosses off
+    #[serde(default)]
+    pub boss_every: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WaveDefinition {
+    #[serde(default)]
+    pub big: u32,
+    #[serde(default)]
+    pub medium: u32,
+    #[serde(default)]
+    pub small: u32,
+    #[serde(default = "one")]
+    pub speed_multiplier: f32,
+    #[serde(default = "one")]
+    pub spin_multiplier: f32,
+    #[serde(default)]
+    pub enemies: Vec<EnemySpawn>,
+}
+
+/// `count` enemies of `kind` joining a wave
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnemySpawn {
+    pub kind: EnemyKind,
+    pub count: u32,
+}
+
+/// How waves past the end of [`WaveTable::waves`] grow,
+/// applied per wave on top of the last defined wave
+#[derive(Debug, Clone, Deserialize)]
+pub struct EndlessGrowth {
+    pub big_per_wave: u32,
+    pub speed_growth: f32,
+    pub spin_growth: f32,
+    pub max_speed_multiplier: f32,
+    /// Extra enemies per wave, taking turns through every
+    /// kind
+    #[serde(default)]
+    pub enemies_per_wave: u32,
+}
+
+fn one() -> f32 {
+    1.
+}
+
+impl WaveTable {
+    pub fn is_boss_wave(&self, number: u32) -> bool {
+        number.checked_rem(self.boss_every) == Some(0)
+    }
+    /// The definition for the 1-based wave `number`
+    pub fn wave(&self, number: u32) -> WaveDefinition {
+        let index = number.saturating_sub(1) as usize;
+        if let Some(wave) = self.waves.get(index) {
+            return wave.clone();
+        }
+        let Some(last) = self.waves.last() else {
+            return WaveDefinition {
+                big: number,
+                medium: 0,
+                small: 0,
+                speed_multiplier: 1.,
+                spin_multiplier: 1.,
+                enemies: Vec::new(),
+            };
+        };
+        let extra = (index + 1 - self.waves.len()) as u32;
+        let mut enemies = last.enemies.clone();
+        let kinds = EnemyKind::all_kinds();
+        for index in 0..extra * self.endless.enemies_per_wave {
+            enemies.push(EnemySpawn {
+                kind: kinds[index as usize % kinds.len()],
+                count: 1,
+            });
+        }
+        WaveDefinition {
+            big: last.big + extra * self.endless.big_per_wave,
+            speed_multiplier: (last.speed_multiplier
+                + extra as f32 * self.endless.speed_growth)
+                .min(self.endless.max_speed_multiplier),
+            spin_multiplier: last.spin_multiplier
+                + extra as f32 * self.endless.spin_growth,
+            enemies,
+            ..last.clone()
+        }
+    }
+}
+
+/// The wave being played. `number` is 0 before the first
+/// wave spawned.
+#[derive(Resource, Debug)]
+pub struct CurrentWave {
+    pub number: u32,
+    pub speed_multiplier: f32,
+    pub spin_multiplier: f32,
+    pub intermission: Timer,
+}
+
+impl Default for CurrentWave {
+    fn default() -> Self {
+        CurrentWave {
+            number: 0,
+            speed_multiplier: 1.,
+            spin_multiplier: 1.,
+            intermission: Timer::default(),
+        }
+    }
+}
+
+impl CurrentWave {
+    /// Resumes at wave `number`, e.g. from a save
+    pub fn resume(number: u32, table: &WaveTable) -> Self {
+        let definition = table.wave(number);
+        CurrentWave {
+            number,
+            speed_multiplier: definition.speed_multiplier,
+            spin_multiplier: definition.spin_multiplier,
+            ..default()
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct WaveStarted {
+    pub number: u32,
+    pub boss: bool,
+}
+
+#[derive(Component)]
+struct WaveBanner(Timer);
+
+const WAVE_BANNER_DURATION: Duration =
+    Duration::from_millis(2000);
+const SPAWN_ATTEMPTS: usize = 32;
+const ENEMY_SAFE_RADIUS_FACTOR: f32 = 1.5;
+
+/// Picks a point inside the wrapping area that is at
+/// least `safe_radius` away from `avoid`
+fn spawn_point(
+    rng: &mut impl Rng,
+    half_size: Vec2,
+    avoid: Vec2,
+    safe_radius: f32,
+) -> Vec2 {
+    let mut candidate = Vec2::ZERO;
+    for _ in 0..SPAWN_ATTEMPTS {
+        candidate = Vec2::new(
+            rng.gen_range(-half_size.x..half_size.x),
+            rng.gen_range(-half_size.y..half_size.y),
+        );
+        if candidate.distance(avoid) >= safe_radius {
+            return candidate;
+        }
+    }
+    // the area is too small to honour the radius, push
+    // the last candidate out to it
+    avoid
+        + (candidate - avoid).normalize_or_zero() * safe_radius
+}
+
+/// Anything left of the current wave, the next one waits
+/// until all of it is gone
+type WaveFoes = Or<(With<Meteor>, With<Enemy>, With<Boss>)>;
+
+/// Where new waves may spawn, the whole play area but
+/// away from the player
+#[derive(SystemParam)]
+struct SpawnArea<'w, 's> {
+    area: PlayArea<'w, 's>,
+    player: Query<'w, 's, &'static Transform, With<Player>>,
+}
+
+impl SpawnArea<'_, '_> {
+    fn point(&self, rng: &mut impl Rng, safe_radius: f32) -> Vec2 {
+        let avoid = self
+            .player
+            .get_single()
+            .map(|transform| transform.translation.xy())
+            .unwrap_or_default();
+        spawn_point(rng, self.area.half_size(), avoid, safe_radius)
+    }
+}
+
+/// Runs the pause between a cleared wave and the next,
+/// the first wave starts right away
+fn tick_intermission(
+    time: Res<Time>,
+    level: CurrentLevel,
+    mut wave: ResMut<CurrentWave>,
+    foes: Query<(), WaveFoes>,
+) {
+    if wave.number == 0 || !foes.is_empty() {
+        return;
+    }
+    let Some(level) = level.get() else {
+        return;
+    };
+    if wave.intermission.duration().is_zero() {
+        wave.intermission = Timer::from_seconds(
+            level.waves.intermission_seconds,
+            TimerMode::Once,
+        );
+    }
+    wave.intermission.tick(time.delta());
+}
+
+fn wave_director(
+    mut commands: Commands,
+    sheets: SpriteSheets,
+    level: CurrentLevel,
+    mut wave: ResMut<CurrentWave>,
+    mut wave_started: EventWriter<WaveStarted>,
+    foes: Query<(), WaveFoes>,
+    spawn_area: SpawnArea,
+) {
+    if !foes.is_empty() {
+        return;
+    }
+    if wave.number > 0 && !wave.intermission.finished() {
+        return;
+    }
+    let Some(level) = level.get() else {
+        warn!("wave_director requires the level to be loaded");
+        return;
+    };
+    let table = &level.waves;
+    let Some(space_sheet) = sheets.space() else {
+        warn!("wave_director requires meteor sprites to be loaded");
+        return;
+    };
+
+    let number = wave.number + 1;
+    let definition = table.wave(number);
+    *wave = CurrentWave {
+        number,
+        speed_multiplier: definition.speed_multiplier,
+        spin_multiplier: definition.spin_multiplier,
+        intermission: Timer::default(),
+    };
+
+    let mut rng = rand::thread_rng();
+    if table.is_boss_wave(number) {
+        let position = spawn_area.point(
+            &mut rng,
+            table.safe_radius * ENEMY_SAFE_RADIUS_FACTOR,
+        );
+        spawn_boss(
+            &mut commands,
+            space_sheet,
+            Transform::from_translation(position.extend(1.)),
+            (number / table.boss_every) as f32,
+        );
+        info!("Starting boss wave {number}");
+        wave_started.send(WaveStarted { number, boss: true });
+        return;
+    }
+
+    let counts = [
+        (definition.big, MeteorType::Big),
+        (definition.medium, MeteorType::Medium),
+        (definition.small, MeteorType::Small),
+    ];
+    for (count, meteor_type) in counts {
+        for _ in 0..count {
+            let position =
+                spawn_area.point(&mut rng, table.safe_radius);
+            commands.spawn(
+                MeteorBundle::from_type(
+                    meteor_type,
+                    Transform::from_translation(
+                        position.extend(1.),
+                    ),
+                    space_sheet,
+                    &level.meteor_sizes,
+                )
+                .with_speed(
+                    definition.speed_multiplier,
+                    definition.spin_multiplier,
+                ),
+            );
+        }
+    }
+
+    // enemies come in from further out than meteors
+    for EnemySpawn { kind, count } in &definition.enemies {
+        for _ in 0..*count {
+            let position = spawn_area.point(
+                &mut rng,
+                table.safe_radius * ENEMY_SAFE_RADIUS_FACTOR,
+            );
+            spawn_enemy(
+                &mut commands,
+                *kind,
+                Transform::from_translation(position.extend(1.)),
+                space_sheet,
+            );
+        }
+    }
+
+    info!("Starting wave {number}");
+    wave_started.send(WaveStarted {
+        number,
+        boss: false,
+    });
+}
+
+fn show_wave_banner(
+    mut commands: Commands,
+    mut events: EventReader<WaveStarted>,
+    banners: Query<Entity, With<WaveBanner>>,
+) {
+    for WaveStarted { number, boss } in events.read() {
+        for entity in &banners {
+            commands.entity(entity).despawn_recursive();
+        }
+        commands
+            .spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.),
+                        height: Val::Percent(100.),
+                        position_type: PositionType::Absolute,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                },
+                WaveBanner(Timer::new(
+                    WAVE_BANNER_DURATION,
+                    TimerMode::Once,
+                )),
+                InGame,
+            ))
+            .with_children(|parent| {
+                parent.spawn(TextBundle::from_section(
+                    if *boss {
+                        format!("Wave {number}: Mothership")
+                    } else {
+                        format!("Wave {number}")
+                    },
+                    TextStyle {
+                        font_size: 60.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ));
+            });
+    }
+}
+
+fn fade_wave_banner(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut banners: Query<(Entity, &mut WaveBanner, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (entity, mut banner, children) in &mut banners {
+        banner.0.tick(time.delta());
+        if banner.0.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let alpha = banner.0.fraction_remaining();
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                for section in &mut text.sections {
+                    section.style.color.set_a(alpha);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn table() -> WaveTable {
+        WaveTable {
+            safe_radius: 250.,
+            intermission_seconds: 2.,
+            waves: vec![
+                WaveDefinition {
+                    big: 1,
+                    medium: 0,
+                    small: 0,
+                    speed_multiplier: 1.,
+                    spin_multiplier: 1.,
+                    enemies: Vec::new(),
+                },
+                WaveDefinition {
+                    big: 2,
+                    medium: 1,
+                    small: 3,
+                    speed_multiplier: 1.2,
+                    spin_multiplier: 1.5,
+                    enemies: vec![EnemySpawn {
+                        kind: EnemyKind::Fighter,
+                        count: 1,
+                    }],
+                },
+            ],
+            endless: EndlessGrowth {
+                big_per_wave: 2,
+                speed_growth: 0.5,
+                spin_growth: 0.25,
+                max_speed_multiplier: 2.,
+                enemies_per_wave: 1,
+            },
+            boss_every: 0,
+        }
+    }
+
+    #[test]
+    fn defined_waves_are_played_as_written() {
+        let table = table();
+        assert_eq!(table.wave(1).big, 1);
+        let second = table.wave(2);
+        assert_eq!((second.big, second.medium, second.small), (2, 1, 3));
+        assert_eq!(second.enemies.len(), 1);
+    }
+
+    #[test]
+    fn endless_waves_grow_from_the_last_one() {
+        let wave = table().wave(4);
+        assert_eq!(wave.big, 2 + 2 * 2);
+        // medium and small meteors carry over unchanged
+        assert_eq!((wave.medium, wave.small), (1, 3));
+        assert_eq!(wave.spin_multiplier, 1.5 + 2. * 0.25);
+        // one extra enemy per wave, taking turns by kind
+        let kinds: Vec<EnemyKind> =
+            wave.enemies.iter().map(|spawn| spawn.kind).collect();
+        assert_eq!(
+            kinds,
+            [EnemyKind::Fighter, EnemyKind::Fighter, EnemyKind::Strafer]
+        );
+    }
+
+    #[test]
+    fn endless_speed_is_capped() {
+        let table = table();
+        assert_eq!(table.wave(3).speed_multiplier, 1.7);
+        assert_eq!(table.wave(10).speed_multiplier, 2.);
+    }
+
+    #[test]
+    fn an_empty_table_grows_by_big_meteors() {
+        let table = WaveTable {
+            waves: Vec::new(),
+            ..table()
+        };
+        assert_eq!(table.wave(3).big, 3);
+        assert_eq!(table.wave(3).speed_multiplier, 1.);
+    }
+
+    #[test]
+    fn every_nth_wave_is_a_boss() {
+        let table = WaveTable {
+            boss_every: 5,
+            ..table()
+        };
+        let bosses: Vec<u32> =
+            (1..=15).filter(|&n| table.is_boss_wave(n)).collect();
+        assert_eq!(bosses, [5, 10, 15]);
+    }
+
+    #[test]
+    fn bosses_can_be_turned_off() {
+        let table = table();
+        assert!((1..=20).all(|n| !table.is_boss_wave(n)));
+    }
+
+    #[test]
+    fn spawn_points_keep_their_distance() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let half_size = Vec2::new(640., 360.);
+        let avoid = Vec2::new(100., -50.);
+        for _ in 0..100 {
+            let point = spawn_point(&mut rng, half_size, avoid, 250.);
+            assert!(point.distance(avoid) >= 250. - 1e-3);
+            assert!(point.x.abs() <= half_size.x);
+            assert!(point.y.abs() <= half_size.y);
+        }
+    }
+
+    #[test]
+    fn a_cramped_area_still_honours_the_radius() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let point =
+            spawn_point(&mut rng, Vec2::splat(10.), Vec2::ZERO, 100.);
+        assert!((point.length() - 100.).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,550 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_xpbd_2d::prelude::*;
+
+use crate::{
+    controller::LaserImpact,
+    effects::{EffectKind, Effects},
+    entities::{
+        boss::BossPart,
+        enemies::Enemy,
+        meteor::Meteor,
+        powerups::{PowerUpEffects, PowerUpKind, BOLT_FIRE_RATE},
+        spaceship::{ShipStats, WeaponLoadout},
+    },
+    utils::{
+        asset_loader::{FontAssets, ImageAssets},
+        kenney_asset::KenneySpriteSheetAsset,
+        pause_system::Pausable,
+    },
+    GameState, InGame, Player,
+};
+
+pub struct WeaponPlugin;
+
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Playing),
+            spawn_weapon_hint,
+        )
+        .add_systems(
+            Update,
+            (
+                (
+                    fit_weapon,
+                    switch_weapon,
+                    fire_weapon,
+                    render_weapon_hint,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+                (steer_homing, move_projectiles, expire_projectiles)
+                    .chain(),
+            )
+                .run_if(resource_equals(Pausable::NotPaused)),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WeaponKind {
+    #[default]
+    Single,
+    Spread,
+    Rapid,
+    Beam,
+    Homing,
+}
+
+impl WeaponKind {
+    pub fn all_kinds() -> [WeaponKind; 5] {
+        [
+            WeaponKind::Single,
+            WeaponKind::Spread,
+            WeaponKind::Rapid,
+            WeaponKind::Beam,
+            WeaponKind::Homing,
+        ]
+    }
+    pub fn next(&self) -> WeaponKind {
+        let kinds = WeaponKind::all_kinds();
+        let index = kinds
+            .iter()
+            .position(|kind| kind == self)
+            .unwrap_or_default();
+        kinds[(index + 1) % kinds.len()]
+    }
+    pub fn name(&self) -> &'static str {
+        match self {
+            WeaponKind::Single => "Laser",
+            WeaponKind::Spread => "Spread shot",
+            WeaponKind::Rapid => "Rapid fire",
+            WeaponKind::Beam => "Charged beam",
+            WeaponKind::Homing => "Homing missiles",
+        }
+    }
+    /// The weapon of this kind a ship with `loadout`
+    /// carries, bigger hulls fire more and faster
+    pub fn weapon(&self, loadout: &WeaponLoadout) -> Weapon {
+        let single = Weapon {
+            kind: *self,
+            sprite: "laserBlue01.png",
+            sheet: ProjectileSheet::Space,
+            speed: 1000.,
+            cooldown: loadout.cooldown,
+            spread: 0.,
+            count: loadout.lasers,
+            damage: 1.,
+            pierce: 0,
+            charge: Duration::ZERO,
+            range: 1200.,
+            lifetime: Duration::from_secs(2),
+            last_shot: None,
+            charging_since: None,
+        };
+        match self {
+            WeaponKind::Single => single,
+            WeaponKind::Spread => Weapon {
+                sprite: "laserGreen11.png",
+                speed: 900.,
+                cooldown: loadout.cooldown * 2,
+                spread: 0.2,
+                count: loadout.lasers + 2,
+                range: 800.,
+                ..single
+            },
+            WeaponKind::Rapid => Weapon {
+                sprite: "laserBlue03.png",
+                speed: 1200.,
+                cooldown: loadout.cooldown / 3,
+                damage: 0.5,
+                range: 900.,
+                ..single
+            },
+            WeaponKind::Beam => Weapon {
+                sprite: "beamLong2.png",
+                speed: 1400.,
+                cooldown: loadout.cooldown * 3,
+                count: 1,
+                damage: 3.,
+                pierce: 3,
+                charge: Duration::from_secs(1),
+                range: 1600.,
+                ..single
+            },
+            WeaponKind::Homing => Weapon {
+                sprite: "spaceMissiles_001.png",
+                sheet: ProjectileSheet::Station,
+                speed: 600.,
+                cooldown: loadout.cooldown * 3,
+                spread: 0.3,
+                damage: 2.,
+                range: 1500.,
+                lifetime: Duration::from_secs(4),
+                ..single
+            },
+        }
+    }
+}
+
+/// The sprite sheet a projectile sprite is on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProjectileSheet {
+    Space,
+    Station,
+}
+
+/// What the ship fires when the player holds Space
+#[derive(Component, Clone, Debug)]
+pub struct Weapon {
+    pub kind: WeaponKind,
+    pub sprite: &'static str,
+    pub sheet: ProjectileSheet,
+    pub speed: f32,
+    pub cooldown: Duration,
+    /// Angle between neighbouring projectiles in radians,
+    /// zero fires them side by side
+    pub spread: f32,
+    pub count: u32,
+    pub damage: f32,
+    /// How many targets a projectile passes through
+    pub pierce: u32,
+    /// Time to fully charge a shot, charged weapons fire
+    /// when Space is let go
+    pub charge: Duration,
+    /// How far a projectile flies before it fizzles out
+    pub range: f32,
+    /// How long a projectile lasts when it never gets
+    /// out of range, homing missiles can circle
+    pub lifetime: Duration,
+    pub last_shot: Option<Duration>,
+    pub charging_since: Option<Duration>,
+}
+
+#[derive(Component)]
+pub struct Projectile {
+    /// Ship velocity the projectile was fired with
+    pub inherited: Vec2,
+    pub speed: f32,
+    pub damage: f32,
+    pub pierce: u32,
+    /// Targets already hit, a shot passing through
+    /// something only damages it once
+    pub hit: Vec<Entity>,
+}
+
+/// Projectiles are kinematic bodies, they fly wherever
+/// they point and pass through what they hit instead of
+/// shoving it
+#[derive(Bundle)]
+pub struct ProjectileBodyBundle {
+    rigid_body: RigidBody,
+    velocity: LinearVelocity,
+    sensor: Sensor,
+}
+
+impl ProjectileBodyBundle {
+    pub fn new(velocity: Vec2) -> ProjectileBodyBundle {
+        ProjectileBodyBundle {
+            rigid_body: RigidBody::Kinematic,
+            velocity: LinearVelocity(velocity),
+            sensor: Sensor,
+        }
+    }
+}
+
+/// Projectiles fizzle out once `timer` finishes or
+/// they get `range` away from `origin`
+#[derive(Component)]
+pub struct ProjectileLifetime {
+    pub timer: Timer,
+    pub origin: Vec2,
+    pub range: f32,
+}
+
+/// Turns a projectile towards the closest meteor or
+/// enemy
+#[derive(Component)]
+pub struct Homing {
+    pub turn_rate: f32, // radians per second
+}
+
+/// Distance between projectiles fired side by side
+const LASER_SPACING: f32 = 20.;
+/// A charged shot let go early still fires at this
+/// fraction of its power
+const MIN_CHARGE: f32 = 0.25;
+const HOMING_TURN_RATE: f32 = 3.;
+const HOMING_RANGE: f32 = 600.;
+
+type RefittedShip = (With<Player>, Changed<ShipStats>);
+
+/// Keeps the weapon in line with the ship it is mounted
+/// on, the kind survives upgrades
+fn fit_weapon(
+    mut commands: Commands,
+    ships: Query<
+        (Entity, &ShipStats, Option<&Weapon>),
+        RefittedShip,
+    >,
+) {
+    for (entity, ship, weapon) in &ships {
+        let kind = weapon.map(|weapon| weapon.kind).unwrap_or_default();
+        commands
+            .entity(entity)
+            .insert(kind.weapon(&ship.loadout));
+    }
+}
+
+/// 1 to 5 pick a weapon, Q cycles through them
+fn switch_weapon(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut ships: Query<(&ShipStats, &mut Weapon), With<Player>>,
+) {
+    let keys = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+    ];
+    for (ship, mut weapon) in &mut ships {
+        let picked = keys
+            .iter()
+            .zip(WeaponKind::all_kinds())
+            .find(|(key, _)| keyboard_input.just_pressed(**key))
+            .map(|(_, kind)| kind);
+        let kind = if keyboard_input.just_pressed(KeyCode::KeyQ) {
+            weapon.kind.next()
+        } else if let Some(kind) = picked {
+            kind
+        } else {
+            continue;
+        };
+        if kind != weapon.kind {
+            *weapon = kind.weapon(&ship.loadout);
+        }
+    }
+}
+
+/// How far ahead of the ship's center the nose sits
+const MUZZLE_OFFSET: f32 = 40.;
+const MUZZLE_FLASH_COLOR: Color = Color::rgb(0.7, 0.9, 1.);
+
+fn fire_weapon(
+    mut commands: Commands,
+    time: Res<Time>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut query: Query<
+        (
+            &Transform,
+            &LinearVelocity,
+            &mut Weapon,
+            Option<&PowerUpEffects>,
+        ),
+        With<Player>,
+    >,
+    images: Res<ImageAssets>,
+    sheets: Res<Assets<KenneySpriteSheetAsset>>,
+    mut particles: Effects,
+) {
+    let Ok((transform, ship_velocity, mut weapon, effects)) =
+        query.get_single_mut()
+    else {
+        return;
+    };
+
+    let now = time.elapsed();
+    let cooldown = if effects.is_some_and(|effects| {
+        effects.is_active(PowerUpKind::Bolt)
+    }) {
+        weapon.cooldown / BOLT_FIRE_RATE
+    } else {
+        weapon.cooldown
+    };
+    let ready = match weapon.last_shot {
+        Some(shot) => now - shot > cooldown,
+        None => true,
+    };
+    let power = if weapon.charge.is_zero() {
+        if !keyboard_input.pressed(KeyCode::Space) || !ready {
+            return;
+        }
+        1.
+    } else {
+        if keyboard_input.just_pressed(KeyCode::Space) && ready {
+            weapon.charging_since = Some(now);
+        }
+        if !keyboard_input.just_released(KeyCode::Space) {
+            return;
+        }
+        let Some(since) = weapon.charging_since.take() else {
+            return;
+        };
+        ((now - since).as_secs_f32()
+            / weapon.charge.as_secs_f32())
+        .clamp(MIN_CHARGE, 1.)
+    };
+    weapon.last_shot = Some(now);
+
+    let sheet = match weapon.sheet {
+        ProjectileSheet::Space => &images.space_sheet,
+        ProjectileSheet::Station => &images.station_sheet,
+    };
+    let sheet = sheets.get(sheet).unwrap();
+    let size = sheet
+        .size_of(weapon.sprite)
+        .expect("projectile sprite to exist");
+    // charged shots grow and pierce more with the charge
+    let (scale, pierce) = if weapon.charge.is_zero() {
+        (sheet.scale, weapon.pierce)
+    } else {
+        (
+            sheet.scale * (0.5 + power),
+            (weapon.pierce as f32 * power).round() as u32,
+        )
+    };
+    let damage = weapon.damage * power;
+    particles.play(
+        EffectKind::MuzzleFlash,
+        transform.translation
+            + transform.rotation * Vec3::new(0., MUZZLE_OFFSET, 0.),
+        MUZZLE_FLASH_COLOR,
+    );
+
+    for index in 0..weapon.count {
+        // projectiles fan out, or sit side by side, centred
+        // on the nose
+        let offset =
+            index as f32 - (weapon.count - 1) as f32 / 2.;
+        let mut projectile_transform =
+            transform.with_scale(Vec3::splat(scale));
+        if weapon.spread == 0. {
+            projectile_transform.translation += transform.rotation
+                * Vec3::new(offset * LASER_SPACING, 0., 0.);
+        } else {
+            projectile_transform.rotate_z(-offset * weapon.spread);
+        }
+        let mut projectile = commands.spawn((
+            SpriteBundle {
+                transform: projectile_transform,
+                texture: sheet.sheet.clone(),
+                ..default()
+            },
+            sheet
+                .atlas(weapon.sprite)
+                .expect("projectile sprite to exist"),
+            Projectile {
+                inherited: ship_velocity.0,
+                speed: weapon.speed,
+                damage,
+                pierce,
+                hit: Vec::new(),
+            },
+            ProjectileLifetime {
+                timer: Timer::new(weapon.lifetime, TimerMode::Once),
+                origin: projectile_transform.translation.xy(),
+                range: weapon.range,
+            },
+            ProjectileBodyBundle::new(
+                ship_velocity.0
+                    + (projectile_transform.rotation * Vec3::Y).xy()
+                        * weapon.speed,
+            ),
+            InGame,
+            Collider::capsule(
+                (size.y - size.x).max(0.),
+                size.x / 2.,
+            ),
+        ));
+        if weapon.kind == WeaponKind::Homing {
+            projectile.insert(Homing {
+                turn_rate: HOMING_TURN_RATE,
+            });
+        }
+    }
+}
+
+/// Anything a missile may lock on to
+type HomingTarget = (
+    Or<(With<Meteor>, With<Enemy>, With<BossPart>)>,
+    Without<Homing>,
+);
+
+fn steer_homing(
+    time: Res<Time>,
+    mut missiles: Query<(&mut Transform, &Homing)>,
+    targets: Query<&GlobalTransform, HomingTarget>,
+) {
+    for (mut transform, homing) in &mut missiles {
+        let position = transform.translation.xy();
+        let Some(target) = targets
+            .iter()
+            .map(|target| target.translation().xy())
+            .filter(|target| {
+                target.distance(position) < HOMING_RANGE
+            })
+            .min_by(|a, b| {
+                a.distance_squared(position)
+                    .total_cmp(&b.distance_squared(position))
+            })
+        else {
+            continue;
+        };
+        let facing = (transform.rotation * Vec3::Y).xy();
+        let turn = facing.angle_between(target - position);
+        let max_turn = homing.turn_rate * time.delta_seconds();
+        transform.rotate_z(turn.clamp(-max_turn, max_turn));
+    }
+}
+
+/// Points each projectile's velocity along its nose,
+/// homing missiles turn every frame
+fn move_projectiles(
+    mut projectiles: Query<(
+        &Transform,
+        &Projectile,
+        &mut LinearVelocity,
+    )>,
+) {
+    for (transform, projectile, mut velocity) in &mut projectiles {
+        let facing_direction = transform.rotation * Vec3::Y;
+        velocity.0 = projectile.inherited
+            + facing_direction.xy() * projectile.speed;
+    }
+}
+
+fn expire_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut projectiles: Query<(
+        Entity,
+        &Transform,
+        &mut ProjectileLifetime,
+    )>,
+    mut laser_impact: EventWriter<LaserImpact>,
+) {
+    for (entity, transform, mut lifetime) in &mut projectiles {
+        lifetime.timer.tick(time.delta());
+        let travelled =
+            transform.translation.xy().distance(lifetime.origin);
+        if lifetime.timer.finished() || travelled > lifetime.range
+        {
+            commands.entity(entity).despawn_recursive();
+            laser_impact.send(LaserImpact {
+                at: transform.translation,
+            });
+        }
+    }
+}
+
+#[derive(Component)]
+struct WeaponHint;
+
+fn spawn_weapon_hint(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: fonts.roboto.clone(),
+                font_size: 20.0,
+                color: Color::WHITE,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            left: Val::Px(20.),
+            bottom: Val::Px(20.),
+            ..default()
+        }),
+        WeaponHint,
+        InGame,
+    ));
+}
+
+fn render_weapon_hint(
+    weapons: Query<&Weapon, (With<Player>, Changed<Weapon>)>,
+    mut hints: Query<&mut Text, With<WeaponHint>>,
+) {
+    let Ok(weapon) = weapons.get_single() else {
+        return;
+    };
+    let charge = if weapon.charge.is_zero() {
+        ""
+    } else {
+        ", hold to charge"
+    };
+    for mut text in &mut hints {
+        text.sections[0].value = format!(
+            "{} x{} ({} damage{charge})\n1-5 or Q to switch",
+            weapon.kind.name(),
+            weapon.count,
+            weapon.damage,
+        );
+    }
+}
@@ -1,3 +1,5 @@
 pub mod button;
+pub mod game_over;
 pub mod menu;
 pub mod settings;
+pub mod shipyard;
@@ -1,6 +1,6 @@
 use bevy::{
     app::AppExit, 
-    ecs::system::Command, 
+    ecs::system::{Command, SystemParam}, 
     prelude::*
 };
 
@@ -12,6 +12,7 @@ use crate::{
             ImageAssets
         },
         kenney_asset::KenneySpriteSheetAsset,
+        save_system::{SaveGame, SelectSaveSlot},
     },
     gameui::settings::{AudioSettings, GameSettings},
     GameState,
@@ -33,11 +34,35 @@ const PRESSED_BUTTON: Color = Color::Hsla {
 #[derive(Component)]
 pub struct TextButton;
 
+/// Text buttons whose interaction changed this frame
+type ChangedTextButton = (Changed<Interaction>, With<TextButton>);
+
+/// The click played when hovering buttons, unless audio
+/// is off
+#[derive(SystemParam)]
+pub struct MenuClick<'w> {
+    settings: Res<'w, GameSettings>,
+    sounds: Res<'w, AudioAssets>,
+}
+
+impl MenuClick<'_> {
+    pub fn play(&self, commands: &mut Commands) {
+        if self.settings.audio == AudioSettings::On {
+            commands.spawn(AudioBundle {
+                source: self.sounds.menu_click.clone(),
+                ..default()
+            });
+        }
+    }
+}
+
 #[derive(Resource, Component, Debug, PartialEq)]
 pub enum MenuPage {
     Main,
     Save,
     Settings,
+    Hangar,
+    Shipyard,
 }
 
 // This system taken from https://github.com/rust-adventure/asteroids/blob/yt-2024-04-01/src/ui/button.rs Thanks to https://github.com/ChristopherBiscardi
@@ -49,13 +74,12 @@ pub fn text_button_system(
             &mut BackgroundColor,
             &Children,
         ),
-        (Changed<Interaction>, With<TextButton>),
+        ChangedTextButton,
     >,
     text_query: Query<&Text>,
     mut exit: EventWriter<AppExit>,
     mut menu_page: ResMut<MenuPage>,
-    settings: Res<GameSettings>,
-    sounds: Res<AudioAssets>,
+    click: MenuClick,
     mut next_state: ResMut<NextState<GameState>>,
 ) {
     for (interaction, mut color, children) in
@@ -73,6 +97,12 @@ pub fn text_button_system(
                         *menu_page = MenuPage::Settings;
                         // Show Settings Page
                     }
+                    "Hangar" => {
+                        *menu_page = MenuPage::Hangar;
+                    }
+                    "Shipyard" => {
+                        *menu_page = MenuPage::Shipyard;
+                    }
                     "Exit" => {
                         exit.send(AppExit);
                     }
@@ -80,28 +110,33 @@ pub fn text_button_system(
                         *menu_page = MenuPage::Main;
                         // Show Main Menu Page
                     }
+                    "Retry" => {
+                        next_state
+                            .set(GameState::Playing);
+                    }
+                    "Main Menu" => {
+                        *menu_page = MenuPage::Main;
+                        next_state.set(GameState::Menu);
+                    }
                     "Play" => {
                         next_state
                             .set(GameState::Playing);
                     }
+                    "Save" => {
+                        commands.add(SaveGame);
+                    }
                     "Slot1" => {
-                        if settings.audio == AudioSettings::ON {
-                            // commands.spawn(AudioBundle Game Audio
-                        }
+                        commands.add(SelectSaveSlot(0));
                         next_state
                             .set(GameState::Playing);
                     }
                     "Slot2" => {
-                        if settings.audio == AudioSettings::ON {
-                            // commands.spawn(AudioBundle Game Audio
-                        }
+                        commands.add(SelectSaveSlot(1));
                         next_state
                             .set(GameState::Playing);
                     }
                     "Slot3" => {
-                        if settings.audio == AudioSettings::ON {
-                            // commands.spawn(AudioBundle Game Audio
-                        }
+                        commands.add(SelectSaveSlot(2));
                         next_state
                             .set(GameState::Playing);
                     }
@@ -113,12 +148,7 @@ pub fn text_button_system(
                 }
             }
             Interaction::Hovered => {
-                if settings.audio == AudioSettings::ON {
-                    commands.spawn(AudioBundle {
-                        source: sounds.menu_click.clone(),
-                        ..default()
-                    });
-                }
+                click.play(&mut commands);
                 *color = HOVERED_BUTTON.into();
             }
             Interaction::None => {
@@ -142,7 +172,7 @@ impl<T: Into<String> + Send + 'static> Command
             .unwrap()
             .alfa_slab_one_regular
             .clone();
-        let space_sheet = {
+        let ui_sheet = {
             let images = world
                 .get_resource::<ImageAssets>()
                 .expect("image assets to have been loaded");
@@ -152,8 +182,8 @@ impl<T: Into<String> + Send + 'static> Command
                 .expect("sprite sheet assets collection to exist");
 
             spritesheets
-                .get(&images.space_sheet)
-                .expect("expect space sheet to have loaded")
+                .get(&images.ui_sheet)
+                .expect("expect ui sheet to have loaded")
         };
 
         world
@@ -167,15 +197,12 @@ impl<T: Into<String> + Send + 'static> Command
                         align_items: AlignItems::Center,
                         ..default()
                     },
-                    image: space_sheet.sheet.clone().into(),
+                    image: ui_sheet.sheet.clone().into(),
                     ..default()
                 },
-                TextureAtlas {
-                    index: 12,
-                    layout: space_sheet
-                        .texture_atlas_layout
-                        .clone(),
-                },
+                ui_sheet
+                    .atlas("metalPanel_blue.png")
+                    .expect("button sprite to exist"),
                 TextButton,
             ))
             .set_parent(self.parent)
@@ -0,0 +1,193 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::{meteor::MeteorDestroyed, score::Score},
+    gameui::{
+        button::SpawnButton,
+        settings::{AudioSettings, GameSettings},
+    },
+    utils::{
+        asset_loader::{AudioAssets, FontAssets, ImageAssets},
+        pause_system::Pausable,
+    },
+    GameState,
+};
+
+pub struct GameOverPlugin;
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SessionStats>()
+            .add_systems(
+                Update,
+                track_time_survived
+                    .run_if(resource_equals(
+                        Pausable::NotPaused,
+                    ))
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                PostUpdate,
+                count_destroyed_meteors
+                    .run_if(resource_equals(
+                        Pausable::NotPaused,
+                    ))
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                (show_game_over, play_game_over_sound),
+            )
+            .add_systems(
+                OnExit(GameState::GameOver),
+                hide_game_over,
+            );
+    }
+}
+
+/// What the current run achieved, shown on the game
+/// over screen
+#[derive(
+    Resource, Debug, Clone, Default, Serialize, Deserialize,
+)]
+pub struct SessionStats {
+    pub meteors_destroyed: u32,
+    pub time_survived: Duration,
+}
+
+#[derive(Component)]
+struct GameOverScreen;
+
+fn track_time_survived(
+    time: Res<Time>,
+    mut stats: ResMut<SessionStats>,
+) {
+    stats.time_survived += time.delta();
+}
+
+fn count_destroyed_meteors(
+    mut events: EventReader<MeteorDestroyed>,
+    mut stats: ResMut<SessionStats>,
+) {
+    stats.meteors_destroyed += events.read().count() as u32;
+}
+
+fn play_game_over_sound(
+    mut commands: Commands,
+    settings: Res<GameSettings>,
+    sounds: Res<AudioAssets>,
+) {
+    if settings.audio == AudioSettings::On {
+        commands.spawn(AudioBundle {
+            source: sounds.game_over.clone(),
+            settings: PlaybackSettings::DESPAWN,
+        });
+    }
+}
+
+fn show_game_over(
+    mut commands: Commands,
+    images: Res<ImageAssets>,
+    fonts: Res<FontAssets>,
+    score: Res<Score>,
+    stats: Res<SessionStats>,
+) {
+    let panel_slicer = TextureSlicer {
+        border: BorderRect::square(20.0),
+        center_scale_mode: SliceScaleMode::Stretch,
+        sides_scale_mode: SliceScaleMode::Stretch,
+        max_corner_scale: 1.0,
+    };
+    let seconds = stats.time_survived.as_secs();
+    let summary = format!(
+        "Score: {}\nMeteors destroyed: {}\nTime survived: {}:{:02}",
+        score.0,
+        stats.meteors_destroyed,
+        seconds / 60,
+        seconds % 60
+    );
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    height: Val::Percent(100.),
+                    width: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    position_type: PositionType::Absolute,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            GameOverScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    ImageBundle {
+                        image: images.panel_glass.clone().into(),
+                        style: Style {
+                            width: Val::Px(360.0),
+                            height: Val::Px(500.0),
+                            flex_direction:
+                                FlexDirection::Column,
+                            justify_content:
+                                JustifyContent::SpaceEvenly,
+                            border: UiRect::all(Val::Px(
+                                10.0,
+                            )),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    ImageScaleMode::Sliced(panel_slicer),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(
+                        TextBundle::from_section(
+                            "Game Over",
+                            TextStyle {
+                                font: fonts
+                                    .alfa_slab_one_regular
+                                    .clone(),
+                                font_size: 45.0,
+                                color: Color::rgb(
+                                    0.1, 0.1, 0.14,
+                                ),
+                            },
+                        )
+                        .with_text_justify(JustifyText::Center),
+                    );
+                    parent.spawn(TextBundle::from_section(
+                        summary,
+                        TextStyle {
+                            font: fonts.roboto.clone(),
+                            font_size: 25.0,
+                            color: Color::BLACK,
+                        },
+                    ));
+                    let entity = parent.parent_entity();
+                    parent.add_command(SpawnButton {
+                        parent: entity,
+                        text: "Retry",
+                    });
+                    parent.add_command(SpawnButton {
+                        parent: entity,
+                        text: "Main Menu",
+                    });
+                });
+        });
+}
+
+fn hide_game_over(
+    mut commands: Commands,
+    query: Query<Entity, With<GameOverScreen>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,12 +1,18 @@
-use bevy::{prelude::*, render::render_resource::Texture};
+use bevy::prelude::*;
 
 use crate::{
+    entities::{
+        spaceship::{ShipColor, ShipLevels},
+        upgrades::UnlockedHull,
+    },
     gameui::{
         button::{text_button_system, MenuPage, SpawnButton},settings::{AudioSettings, GameSettings},
+        shipyard::spawn_shipyard_page,
     }, 
     utils::{
-    asset_loader::{AudioAssets, FontAssets, ImageAssets}, 
-    kenney_asset::KenneySpriteSheetAsset
+    asset_loader::{FontAssets, ImageAssets, SpriteSheets}, 
+    kenney_asset::KenneySpriteSheetAsset,
+    save_system::{delete_slot, format_timestamp, read_slot, SAVE_SLOTS},
     }, 
     GameState
 }; 
@@ -17,6 +23,29 @@ pub struct MainMenu;
 #[derive(Component)]
 pub struct AudioSettingsCheckbox;
 
+/// Text under a save slot button showing what the slot
+/// holds
+#[derive(Component)]
+pub struct SaveSlotInfo(pub usize);
+
+#[derive(Component)]
+pub struct DeleteSaveSlotButton(pub usize);
+
+/// Picks the hull shown in the hangar
+#[derive(Component)]
+pub struct HangarShipButton(pub ShipLevels);
+
+/// Picks the paint job shown in the hangar
+#[derive(Component)]
+pub struct HangarColorButton(pub ShipColor);
+
+/// The hangar's large picture of the selected ship
+#[derive(Component)]
+pub struct HangarPreview;
+
+#[derive(Component)]
+pub struct HangarStats;
+
 
 pub struct MainMenuPlugin;
 impl Plugin for MainMenuPlugin {
@@ -42,6 +71,9 @@ impl Plugin for MainMenuPlugin {
             (
                 change_menu,
                 audio_state,
+                refresh_save_slots,
+                delete_save_slot,
+                (select_hangar_ship, refresh_hangar).chain(),
             )
                 .run_if(in_state(GameState::Menu)),
         );
@@ -68,7 +100,8 @@ pub fn main_menu(
     fonts: Res<FontAssets>,
     sheets: Res<Assets<KenneySpriteSheetAsset>>,
 ) {
-    let space_sheet =sheets.get(&images.space_sheet).unwrap();
+    let ui_sheet = sheets.get(&images.ui_sheet).unwrap();
+    let space_sheet = sheets.get(&images.space_sheet).unwrap();
     commands
         .spawn((
             NodeBundle {
@@ -122,6 +155,10 @@ pub fn main_menu(
                         parent: entity,
                         text: "New Game"
                     });
+                    parent.add_command(SpawnButton{
+                        parent: entity,
+                        text: "Hangar"
+                    });
                     parent.add_command(SpawnButton{
                         parent: entity,
                         text: "Settings"
@@ -231,22 +268,305 @@ pub fn main_menu(
                     parent: entity,
                     text: "Back"
                 });
-                parent.add_command(SpawnButton{
-                    parent: entity,
-                    text: "Slot1"
-                });
-                parent.add_command(SpawnButton{
-                    parent: entity,
-                    text: "Slot2"
-                });
-                parent.add_command(SpawnButton{
-                    parent: entity,
-                    text: "Slot3"
-                });
+                for slot in 0..SAVE_SLOTS {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                flex_direction:
+                                    FlexDirection::Column,
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent
+                                .spawn(NodeBundle {
+                                    style: Style {
+                                        column_gap: Val::Px(10.0),
+                                        ..default()
+                                    },
+                                    ..default()
+                                })
+                                .with_children(|parent| {
+                                    let entity = parent.parent_entity();
+                                    parent.add_command(SpawnButton{
+                                        parent: entity,
+                                        text: format!("Slot{}", slot + 1)
+                                    });
+                                    parent
+                                        .spawn((
+                                            ButtonBundle {
+                                                style: Style {
+                                                    width: Val::Px(65.0),
+                                                    height: Val::Px(65.0),
+                                                    flex_shrink: 0.,
+                                                    justify_content:
+                                                        JustifyContent::Center,
+                                                    align_items:
+                                                        AlignItems::Center,
+                                                    ..default()
+                                                },
+                                                image: ui_sheet.sheet.clone().into(),
+                                                ..default()
+                                            },
+                                            ui_sheet
+                                                .atlas("metalPanel_red.png")
+                                                .expect("button sprite to exist"),
+                                            DeleteSaveSlotButton(slot),
+                                        ))
+                                        .with_children(|parent| {
+                                            parent.spawn(TextBundle::from_section(
+                                                "X",
+                                                TextStyle {
+                                                    font: fonts.alfa_slab_one_regular.clone(),
+                                                    font_size: 40.0,
+                                                    color: Color::rgb(0.1, 0.1, 0.14),
+                                                },
+                                            ));
+                                        });
+                                });
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    "Empty",
+                                    TextStyle {
+                                        font: fonts.roboto.clone(),
+                                        font_size: 18.0,
+                                        color: Color::BLACK,
+                                    },
+                                ),
+                                SaveSlotInfo(slot),
+                            ));
+                        });
+                }
             });
+            parent
+                .spawn((
+                    ImageBundle {
+                        image: images.panel_glass.clone().into(),
+                        visibility: Visibility::Hidden,
+                        style: Style {
+                            width: Val::Px(360.0),
+                            height: Val::Px(560.0),
+                            flex_direction:
+                                FlexDirection::Column,
+                            justify_content:
+                                JustifyContent::SpaceBetween,
+                            align_items: AlignItems::Center,
+                            border: UiRect::all(Val::Px(
+                                10.0,
+                            )),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    ImageScaleMode::Sliced(panel_slicer.clone()),
+                    MenuPage::Hangar,
+                ))
+                .with_children(|parent| {
+                    let entity = parent.parent_entity();
+                    parent.add_command(SpawnButton {
+                        parent: entity,
+                        text: "Back",
+                    });
+                    parent.spawn((
+                        ImageBundle {
+                            style: Style {
+                                width: Val::Px(112.0),
+                                height: Val::Px(75.0),
+                                ..default()
+                            },
+                            image: space_sheet.sheet.clone().into(),
+                            ..default()
+                        },
+                        space_sheet
+                            .atlas(&ShipLevels::Initial.base_sprite_name(
+                                ShipColor::Blue,
+                            ))
+                            .expect("ship sprite to exist"),
+                        HangarPreview,
+                    ));
+                    parent.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font: fonts.roboto.clone(),
+                                font_size: 20.0,
+                                color: Color::BLACK,
+                            },
+                        )
+                        .with_text_justify(JustifyText::Center),
+                        HangarStats,
+                    ));
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                column_gap: Val::Px(10.0),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for ship in ShipLevels::all_ships() {
+                                parent.spawn((
+                                    ButtonBundle {
+                                        style: Style {
+                                            width: Val::Px(66.0),
+                                            height: Val::Px(50.0),
+                                            ..default()
+                                        },
+                                        image: space_sheet.sheet.clone().into(),
+                                        ..default()
+                                    },
+                                    space_sheet
+                                        .atlas(&ship.life_sprite_name(
+                                            ShipColor::Blue,
+                                        ))
+                                        .expect("life sprite to exist"),
+                                    HangarShipButton(ship),
+                                ));
+                            }
+                        });
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                column_gap: Val::Px(10.0),
+                                margin: UiRect::bottom(Val::Px(10.0)),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            for color in ShipColor::all_colors() {
+                                parent.spawn((
+                                    ButtonBundle {
+                                        style: Style {
+                                            width: Val::Px(40.0),
+                                            height: Val::Px(40.0),
+                                            ..default()
+                                        },
+                                        image: ui_sheet.sheet.clone().into(),
+                                        ..default()
+                                    },
+                                    ui_sheet
+                                        .atlas(hangar_swatch(color))
+                                        .expect("swatch sprite to exist"),
+                                    HangarColorButton(color),
+                                ));
+                            }
+                        });
+                    parent.add_command(SpawnButton {
+                        parent: entity,
+                        text: "Shipyard",
+                    });
+                });
+            spawn_shipyard_page(
+                parent,
+                &images,
+                &fonts,
+                ui_sheet,
+                panel_slicer.clone(),
+            );
         });
 }
 
+/// The UI pack has no orange, yellow is the closest
+fn hangar_swatch(color: ShipColor) -> &'static str {
+    match color {
+        ShipColor::Blue => "dotBlue.png",
+        ShipColor::Green => "dotGreen.png",
+        ShipColor::Orange => "dotYellow.png",
+        ShipColor::Red => "dotRed.png",
+    }
+}
+
+fn select_hangar_ship(
+    ships: Query<
+        (&Interaction, &HangarShipButton),
+        Changed<Interaction>,
+    >,
+    colors: Query<
+        (&Interaction, &HangarColorButton),
+        Changed<Interaction>,
+    >,
+    unlocked: Res<UnlockedHull>,
+    mut settings: ResMut<GameSettings>,
+) {
+    for (interaction, HangarShipButton(ship)) in &ships {
+        // hulls open up once a run upgraded to them
+        if interaction == &Interaction::Pressed
+            && *ship <= unlocked.0
+        {
+            settings.ship = *ship;
+            // a stock hull replaces any shipyard design
+            settings.design = None;
+        }
+    }
+    for (interaction, HangarColorButton(color)) in &colors {
+        if interaction == &Interaction::Pressed {
+            settings.ship_color = *color;
+        }
+    }
+}
+
+/// Shows the selected hull in its selected color, and
+/// tints the hull buttons to match
+fn refresh_hangar(
+    settings: Res<GameSettings>,
+    menu: Res<MenuPage>,
+    mut preview: Query<&mut TextureAtlas, With<HangarPreview>>,
+    mut ship_buttons: Query<
+        (&HangarShipButton, &mut TextureAtlas, &mut BackgroundColor),
+        Without<HangarPreview>,
+    >,
+    mut stats: Query<&mut Text, With<HangarStats>>,
+    sheets: SpriteSheets,
+    unlocked: Res<UnlockedHull>,
+) {
+    if !settings.is_changed()
+        && !menu.is_changed()
+        && !unlocked.is_changed()
+    {
+        return;
+    }
+    let Some(space_sheet) = sheets.space() else {
+        return;
+    };
+    let ship = settings.ship;
+    let color = settings.ship_color;
+    for mut atlas in &mut preview {
+        *atlas = space_sheet
+            .atlas(&ship.base_sprite_name(color))
+            .expect("ship sprite to exist");
+    }
+    for (HangarShipButton(button_ship), mut atlas, mut background) in
+        &mut ship_buttons
+    {
+        *atlas = space_sheet
+            .atlas(&button_ship.life_sprite_name(color))
+            .expect("life sprite to exist");
+        // dim the hulls that are not selected, and the
+        // locked ones even more
+        *background = if *button_ship == ship {
+            Color::WHITE
+        } else if *button_ship <= unlocked.0 {
+            Color::rgba(1., 1., 1., 0.4)
+        } else {
+            Color::rgba(0.2, 0.2, 0.2, 0.4)
+        }
+        .into();
+    }
+    let speed = ship.base_ship_speed();
+    let loadout = ship.weapon_loadout();
+    for mut text in &mut stats {
+        text.sections[0].value = format!(
+            "{ship:?}\nSpeed: {:.0}\nTurn rate: {:.0}°/s\nLasers: {}",
+            speed.movement_speed,
+            speed.rotation_speed.to_degrees(),
+            loadout.lasers,
+        );
+    }
+}
+
 fn change_menu(
     menu: Res<MenuPage>,
     mut menu_pages: Query<(&MenuPage, &mut Visibility)>,
@@ -263,35 +583,92 @@ fn change_menu(
     }
 }
 
+/// The audio checkbox, when it was just clicked or hovered
+type ChangedAudioCheckbox = (
+    Changed<Interaction>,
+    With<Button>,
+    With<AudioSettingsCheckbox>,
+);
+
 fn audio_state(
-    commands: Commands,
     mut interaction_query: Query<
         (&Interaction, &mut UiImage),
-        (
-            Changed<Interaction>,
-            With<Button>,
-            With<AudioSettingsCheckbox>,
-        ),
+        ChangedAudioCheckbox,
     >,
     images: Res<ImageAssets>,
     mut settings: ResMut<GameSettings>,
-    sounds: Res<AudioAssets>,
 ) {
     for (interaction, mut image) in &mut interaction_query {
         if interaction == &Interaction::Pressed {
-            //if settings.audio == AudioSettings::ON {}
+            //if settings.audio == AudioSettings::On {}
             settings.audio = match settings.audio {
-                AudioSettings::ON => AudioSettings::OFF,
-                AudioSettings::OFF => AudioSettings::ON,
+                AudioSettings::On => AudioSettings::Off,
+                AudioSettings::Off => AudioSettings::On,
             };
             *image = UiImage::new(match settings.audio {
-                AudioSettings::ON => {
+                AudioSettings::On => {
                     images.box_checked.clone()
                 }
-                AudioSettings::OFF => {
+                AudioSettings::Off => {
                     images.box_unchecked.clone()
                 }
             });
         }
     }
-}
\ No newline at end of file
+}
+fn slot_summary(slot: usize) -> String {
+    match read_slot(slot) {
+        Ok(Some(save)) => {
+            let ship = save
+                .player
+                .map(|player| format!("{:?}", player.ship_level))
+                .unwrap_or_else(|| "No ship".to_string());
+            format!(
+                "{} | Lives {} | {} | {} pts",
+                format_timestamp(save.saved_at),
+                save.lives,
+                ship,
+                save.score
+            )
+        }
+        Ok(None) => "Empty".to_string(),
+        Err(error) => {
+            warn!("Could not read save slot {}: {error}", slot + 1);
+            "Unreadable save".to_string()
+        }
+    }
+}
+
+fn refresh_save_slots(
+    menu: Res<MenuPage>,
+    mut slot_infos: Query<(&SaveSlotInfo, &mut Text)>,
+) {
+    if !menu.is_changed() || *menu != MenuPage::Save {
+        return;
+    }
+    for (SaveSlotInfo(slot), mut text) in &mut slot_infos {
+        text.sections[0].value = slot_summary(*slot);
+    }
+}
+
+fn delete_save_slot(
+    interaction_query: Query<
+        (&Interaction, &DeleteSaveSlotButton),
+        Changed<Interaction>,
+    >,
+    mut slot_infos: Query<(&SaveSlotInfo, &mut Text)>,
+) {
+    for (interaction, DeleteSaveSlotButton(slot)) in &interaction_query {
+        if interaction != &Interaction::Pressed {
+            continue;
+        }
+        if let Err(error) = delete_slot(*slot) {
+            error!("Could not delete save slot {}: {error}", slot + 1);
+        }
+        for (info, mut text) in &mut slot_infos {
+            if info.0 == *slot {
+                text.sections[0].value = slot_summary(*slot);
+            }
+        }
+    }
+}
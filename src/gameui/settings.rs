@@ -1,5 +1,10 @@
 use bevy::prelude::*;
 
+use crate::entities::{
+    ship_builder::ShipDesign,
+    spaceship::{ShipColor, ShipLevels},
+};
+
 pub struct SettingsPlugin;
 
 impl Plugin for SettingsPlugin {
@@ -10,19 +15,28 @@ impl Plugin for SettingsPlugin {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AudioSettings {
-    ON,
-    OFF,
+    On,
+    Off,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Resource)]
 pub struct GameSettings {
     pub audio: AudioSettings,
+    /// Hull picked in the hangar, new runs start with it
+    pub ship: ShipLevels,
+    pub ship_color: ShipColor,
+    /// Flown instead of the hangar hull when set from the
+    /// shipyard
+    pub design: Option<ShipDesign>,
 }
 
 impl Default for GameSettings {
     fn default() -> Self {
         GameSettings {
-            audio: AudioSettings::ON,
+            audio: AudioSettings::On,
+            ship: ShipLevels::Initial,
+            ship_color: ShipColor::Blue,
+            design: None,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,398 @@
+use bevy::prelude::*;
+
+use crate::{
+    entities::ship_builder::{PartColor, PartKind, ShipDesign},
+    gameui::{
+        button::{MenuPage, SpawnButton},
+        settings::GameSettings,
+    },
+    utils::{
+        asset_loader::{FontAssets, ImageAssets, SpriteSheets},
+        kenney_asset::KenneySpriteSheetAsset,
+        save_system::{read_designs, write_designs},
+    },
+    GameState,
+};
+
+pub struct ShipyardPlugin;
+
+impl Plugin for ShipyardPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditedDesign>()
+            .add_systems(Startup, load_designs)
+            .add_systems(
+                Update,
+                (shipyard_actions, refresh_shipyard)
+                    .chain()
+                    .run_if(in_state(GameState::Menu)),
+            );
+    }
+}
+
+/// Designs saved from the shipyard, `browse` is the one
+/// last loaded into the editor
+#[derive(Resource, Default)]
+pub struct SavedDesigns {
+    pub designs: Vec<ShipDesign>,
+    pub browse: usize,
+}
+
+/// The design on the shipyard's workbench
+#[derive(Resource, Default)]
+pub struct EditedDesign(pub ShipDesign);
+
+#[derive(Component, Clone, Copy, Debug)]
+pub enum ShipyardAction {
+    Cycle(PartKind, i32),
+    CycleColor(i32),
+    Save,
+    Load,
+    Fly,
+}
+
+#[derive(Component)]
+struct ShipyardPreview;
+
+#[derive(Component)]
+struct ShipyardStats;
+
+/// Preview scale, parts are laid out at sheet size
+const PREVIEW_SCALE: f32 = 0.6;
+const PREVIEW_SIZE: Vec2 = Vec2::new(220., 120.);
+
+fn load_designs(mut commands: Commands) {
+    let designs = read_designs().unwrap_or_else(|error| {
+        warn!("Could not read ship designs: {error}");
+        Vec::new()
+    });
+    commands.insert_resource(SavedDesigns {
+        designs,
+        browse: 0,
+    });
+}
+
+/// Spawns the Shipyard page of the main menu under
+/// `parent`
+pub fn spawn_shipyard_page(
+    parent: &mut ChildBuilder,
+    images: &ImageAssets,
+    fonts: &FontAssets,
+    ui_sheet: &KenneySpriteSheetAsset,
+    panel_slicer: TextureSlicer,
+) {
+    parent
+        .spawn((
+            ImageBundle {
+                image: images.panel_glass.clone().into(),
+                visibility: Visibility::Hidden,
+                style: Style {
+                    width: Val::Px(360.0),
+                    height: Val::Px(560.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::Center,
+                    position_type: PositionType::Absolute,
+                    border: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            ImageScaleMode::Sliced(panel_slicer),
+            MenuPage::Shipyard,
+        ))
+        .with_children(|parent| {
+            let entity = parent.parent_entity();
+            parent.add_command(SpawnButton {
+                parent: entity,
+                text: "Back",
+            });
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(PREVIEW_SIZE.x),
+                        height: Val::Px(PREVIEW_SIZE.y),
+                        ..default()
+                    },
+                    ..default()
+                },
+                ShipyardPreview,
+            ));
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: fonts.roboto.clone(),
+                        font_size: 18.0,
+                        color: Color::BLACK,
+                    },
+                )
+                .with_text_justify(JustifyText::Center),
+                ShipyardStats,
+            ));
+            let rows = [
+                ("Cockpit", Some(PartKind::Cockpit)),
+                ("Wings", Some(PartKind::Wing)),
+                ("Engine", Some(PartKind::Engine)),
+                ("Guns", Some(PartKind::Gun)),
+                ("Color", None),
+            ];
+            for (label, kind) in rows {
+                let action = |step| match kind {
+                    Some(kind) => ShipyardAction::Cycle(kind, step),
+                    None => ShipyardAction::CycleColor(step),
+                };
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.),
+                            justify_content:
+                                JustifyContent::SpaceBetween,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        spawn_shipyard_button(
+                            parent,
+                            ui_sheet,
+                            fonts,
+                            "<",
+                            action(-1),
+                        );
+                        parent.spawn(TextBundle::from_section(
+                            label,
+                            TextStyle {
+                                font: fonts.roboto.clone(),
+                                font_size: 22.0,
+                                color: Color::BLACK,
+                            },
+                        ));
+                        spawn_shipyard_button(
+                            parent,
+                            ui_sheet,
+                            fonts,
+                            ">",
+                            action(1),
+                        );
+                    });
+            }
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.),
+                        justify_content: JustifyContent::SpaceBetween,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    spawn_shipyard_button(
+                        parent,
+                        ui_sheet,
+                        fonts,
+                        "Save",
+                        ShipyardAction::Save,
+                    );
+                    spawn_shipyard_button(
+                        parent,
+                        ui_sheet,
+                        fonts,
+                        "Load",
+                        ShipyardAction::Load,
+                    );
+                    spawn_shipyard_button(
+                        parent,
+                        ui_sheet,
+                        fonts,
+                        "Fly",
+                        ShipyardAction::Fly,
+                    );
+                });
+        });
+}
+
+fn spawn_shipyard_button(
+    parent: &mut ChildBuilder,
+    ui_sheet: &KenneySpriteSheetAsset,
+    fonts: &FontAssets,
+    label: &str,
+    action: ShipyardAction,
+) {
+    let width = if label.len() > 1 { 100. } else { 40. };
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    width: Val::Px(width),
+                    height: Val::Px(40.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                image: ui_sheet.sheet.clone().into(),
+                ..default()
+            },
+            ui_sheet
+                .atlas("metalPanel_blue.png")
+                .expect("button sprite to exist"),
+            action,
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                label,
+                TextStyle {
+                    font: fonts.alfa_slab_one_regular.clone(),
+                    font_size: 24.0,
+                    color: Color::rgb(0.1, 0.1, 0.14),
+                },
+            ));
+        });
+}
+
+fn shipyard_actions(
+    interaction_query: Query<
+        (&Interaction, &ShipyardAction),
+        Changed<Interaction>,
+    >,
+    mut edited: ResMut<EditedDesign>,
+    mut saved: ResMut<SavedDesigns>,
+    mut settings: ResMut<GameSettings>,
+) {
+    for (interaction, action) in &interaction_query {
+        if interaction != &Interaction::Pressed {
+            continue;
+        }
+        let design = &mut edited.0;
+        match *action {
+            ShipyardAction::Cycle(kind, step) => {
+                design.cycle(kind, step);
+            }
+            ShipyardAction::CycleColor(step) => {
+                let colors = PartColor::all_colors();
+                let current = colors
+                    .iter()
+                    .position(|color| *color == design.color)
+                    .unwrap_or_default() as i32;
+                design.color = colors[(current + step)
+                    .rem_euclid(colors.len() as i32)
+                    as usize];
+            }
+            ShipyardAction::Save => {
+                // saving a loaded design overwrites it
+                match saved
+                    .designs
+                    .iter()
+                    .position(|saved| saved.name == design.name)
+                {
+                    Some(index) => {
+                        saved.designs[index] = design.clone();
+                    }
+                    None => {
+                        design.name = format!(
+                            "Design {}",
+                            saved.designs.len() + 1
+                        );
+                        saved.designs.push(design.clone());
+                    }
+                }
+                if let Err(error) = write_designs(&saved.designs) {
+                    error!("Could not save ship designs: {error}");
+                }
+            }
+            ShipyardAction::Load => {
+                if saved.designs.is_empty() {
+                    continue;
+                }
+                saved.browse =
+                    (saved.browse + 1) % saved.designs.len();
+                *design = saved.designs[saved.browse].clone();
+            }
+            ShipyardAction::Fly => {
+                settings.design = Some(design.clone());
+            }
+        }
+    }
+}
+
+/// Rebuilds the preview and stats whenever the design
+/// on the workbench changes
+fn refresh_shipyard(
+    mut commands: Commands,
+    edited: Res<EditedDesign>,
+    settings: Res<GameSettings>,
+    sheets: SpriteSheets,
+    preview: Query<Entity, With<ShipyardPreview>>,
+    mut stats: Query<&mut Text, With<ShipyardStats>>,
+    mut shown: Local<Option<ShipDesign>>,
+) {
+    let Ok(preview) = preview.get_single() else {
+        return;
+    };
+    if shown.as_ref() == Some(&edited.0) && !settings.is_changed()
+    {
+        return;
+    }
+    let Some(space_sheet) = sheets.space() else {
+        return;
+    };
+    let design = &edited.0;
+    *shown = Some(design.clone());
+
+    let mut layout = design.layout(space_sheet);
+    // later nodes draw on top
+    layout.sort_by(|a, b| a.offset.z.total_cmp(&b.offset.z));
+    commands.entity(preview).despawn_descendants().with_children(
+        |parent| {
+            for placement in layout {
+                let name = design.sprite_name(placement.kind);
+                let size = space_sheet
+                    .size_of(&name)
+                    .expect("ship part sprite to exist")
+                    * PREVIEW_SCALE;
+                let center = PREVIEW_SIZE / 2.
+                    + Vec2::new(
+                        placement.offset.x,
+                        -placement.offset.y,
+                    ) * PREVIEW_SCALE;
+                parent.spawn((
+                    ImageBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Val::Px(center.x - size.x / 2.),
+                            top: Val::Px(center.y - size.y / 2.),
+                            width: Val::Px(size.x),
+                            height: Val::Px(size.y),
+                            ..default()
+                        },
+                        image: UiImage {
+                            texture: space_sheet.sheet.clone(),
+                            flip_x: placement.flip,
+                            flip_y: false,
+                        },
+                        ..default()
+                    },
+                    space_sheet
+                        .atlas(&name)
+                        .expect("ship part sprite to exist"),
+                ));
+            }
+        },
+    );
+
+    let ship = design.stats();
+    let flying = if settings.design.as_ref() == Some(design) {
+        " (flying)"
+    } else {
+        ""
+    };
+    for mut text in &mut stats {
+        text.sections[0].value = format!(
+            "{}{flying}\nSpeed {:.0} | Turn {:.0}°/s | Lasers {}",
+            design.name,
+            ship.speed.movement_speed,
+            ship.speed.rotation_speed.to_degrees(),
+            ship.loadout.lasers,
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use bevy::{prelude::*, render::camera::{self, ScalingMode}, transform::commands};
+use bevy::{ecs::system::SystemParam, prelude::*};
 use bevy_xpbd_2d::prelude::*;
 use bevy_hanabi::prelude::*;
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
@@ -6,30 +6,52 @@ use bevy_inspector_egui::quick::WorldInspectorPlugin;
 mod utils;
 mod gameui;
 mod movement;
+mod animation;
+mod effects;
 mod entities;
 mod controller;
 use crate::{
     utils::{
         asset_loader::AssetsPlugin,
-        asset_loader::ImageAssets, 
+        debug_overlay::DebugOverlayPlugin,
+        screen_shake::ScreenShakePlugin,
+        asset_loader::SpriteSheets,
         kenney_asset::KenneySpriteSheetAsset,
         pause_system::PausePlugin,
         pause_system::Pausable,
+        level_asset::{spawn_level_environment, CurrentLevel, LevelAsset},
+        save_system::{PendingLoad, SavePlugin},
     },
-    gameui::settings::SettingsPlugin,
+    gameui::settings::{GameSettings, SettingsPlugin},
     gameui::menu::MainMenuPlugin,
+    gameui::shipyard::ShipyardPlugin,
+    gameui::game_over::{GameOverPlugin, SessionStats},
     movement::MovementPlugin,
+    animation::AnimationPlugin,
+    effects::EffectsPlugin,
     //movement::MovementWrapper,
     entities::{
         lives::LifePlugin,
-        spaceship::ShipPlugin,
-        spaceship::ShipBundle,
-        spaceship::ShipLevels,
-        spaceship::EngineFire,
+        spaceship::{RespawnCountdown, ShipPlugin},
+        lives::Lives,
+        ship_builder::{spawn_selected_ship, ShipBuilderPlugin},
         meteor::MeteorPlugin,
         meteor::MeteorBundle,
-        collisions::laser_meteor_collision,
+        collisions::laser_collision,
         collisions::ship_meteor_collision,
+        collisions::ship_ore_collision,
+        collisions::ship_power_up_collision,
+        collisions::enemy_ship_collision,
+        ore::{Cargo, OrePlugin},
+        score::{Combo, Score, ScorePlugin},
+        waves::{CurrentWave, WavePlugin},
+        station::StationPlugin,
+        upgrades::UpgradePlugin,
+        weapon::WeaponPlugin,
+        powerups::PowerUpPlugin,
+        enemies::EnemyPlugin,
+        boss::BossPlugin,
+        health::HealthPlugin,
     },
     controller::ControlsPlugin,
 };   
@@ -42,11 +64,17 @@ pub enum GameState {
     AssetLoading,
     Menu,
     Playing,
+    GameOver,
 }
 
 #[derive(Component)]
 pub struct Player;
 
+/// Marks everything spawned for a run (ship, meteors,
+/// lasers, pickups) so it is torn down when play ends
+#[derive(Component, Default)]
+pub struct InGame;
+
 fn main() {
     App::new()
         .insert_resource(ClearColor(Color::rgb(
@@ -64,25 +92,63 @@ fn main() {
             PhysicsDebugPlugin::default(),
             WorldInspectorPlugin::new(),
             HanabiPlugin,
-
+            AnimationPlugin,
+            EffectsPlugin,
+        ))
+        .add_plugins((
             AssetsPlugin,
             SettingsPlugin,
             MainMenuPlugin,
+            GameOverPlugin,
             PausePlugin,
+            SavePlugin,
             MovementPlugin,
+            ControlsPlugin,
+            ShipyardPlugin,
+            DebugOverlayPlugin,
+            ScreenShakePlugin,
+        ))
+        .add_plugins((
             ShipPlugin,
             LifePlugin,
             MeteorPlugin,
-            ControlsPlugin,
+            OrePlugin,
+            ScorePlugin,
+            WavePlugin,
+            StationPlugin,
+            UpgradePlugin,
+            ShipBuilderPlugin,
+            WeaponPlugin,
+            PowerUpPlugin,
+            EnemyPlugin,
+            BossPlugin,
+            HealthPlugin,
         ))
+        // meteors and the ship drift, nothing falls
+        .insert_resource(Gravity::ZERO)
         .init_state::<GameState>()
         .add_systems(Startup, setup_camera)
-        .add_systems(OnEnter(GameState::Playing), test_game_start)
+        .add_systems(
+            OnEnter(GameState::Playing),
+            (
+                test_game_start
+                    .run_if(not(resource_exists::<PendingLoad>)),
+                restore_saved_game
+                    .run_if(resource_exists::<PendingLoad>),
+            ),
+        )
+        .add_systems(
+            OnExit(GameState::Playing),
+            despawn_gameplay_entities,
+        )
         .add_systems(
             Update,
             (
-                laser_meteor_collision,
+                laser_collision,
                 ship_meteor_collision,
+                ship_ore_collision,
+                ship_power_up_collision,
+                enemy_ship_collision,
             )
                 .run_if(in_state(GameState::Playing))
                 .run_if(resource_equals(
@@ -96,105 +162,191 @@ fn main() {
 pub struct CameraFollowsPlayer;
 
 fn setup_camera(mut commands: Commands) {
-    let mut camera_bundle = Camera2dBundle::default();
+    let camera_bundle = Camera2dBundle::default();
     commands.spawn(camera_bundle).insert(CameraFollowsPlayer);
 }
+fn despawn_gameplay_entities(
+    mut commands: Commands,
+    query: Query<Entity, With<InGame>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// What a game is built from: the level and the sheets
+/// its meteors, ship and stations come from
+#[derive(SystemParam)]
+struct LevelStart<'w> {
+    sheets: SpriteSheets<'w>,
+    level: CurrentLevel<'w>,
+    next_state: ResMut<'w, NextState<GameState>>,
+}
+
+impl LevelStart<'_> {
+    /// The space sheet, station sheet and level. When one
+    /// is missing, say so and go back to the menu, a
+    /// level file that fails to parse ends up here.
+    fn get(
+        &mut self,
+    ) -> Option<(
+        &KenneySpriteSheetAsset,
+        &KenneySpriteSheetAsset,
+        &LevelAsset,
+    )> {
+        let missing = match (
+            self.sheets.space(),
+            self.sheets.station(),
+            self.level.get(),
+        ) {
+            (Some(space_sheet), Some(station_sheet), Some(level)) => {
+                return Some((space_sheet, station_sheet, level));
+            }
+            (_, _, None) => "the level",
+            (None, _, _) => "the space sheet",
+            (_, None, _) => "the station sheet",
+        };
+        error!("Cannot start a game without {missing}, returning to the menu");
+        self.next_state.set(GameState::Menu);
+        None
+    }
+}
+
 fn test_game_start(
     mut commands: Commands,
-    images: Res<ImageAssets>,
-    sheets: Res<Assets<KenneySpriteSheetAsset>>,
+    mut start: LevelStart,
+    settings: Res<GameSettings>,
+    mut clear_color: ResMut<ClearColor>,
+    mut lives: ResMut<Lives>,
 ){
-    let space_sheet = sheets.get(&images.space_sheet).unwrap();
-    let engine_fire = commands
-    .spawn((
-        SpriteBundle {
-            transform: Transform::from_xyz(
-                0., -60., 1.,
-            ),
-            texture: space_sheet.sheet.clone(),
-            sprite: Sprite {
-                flip_y: true,
-                ..default()
-            },
-            visibility: Visibility::Hidden,
-            ..default()
-        },
-        TextureAtlas {
-            index: 74,
-            layout: space_sheet
-                .texture_atlas_layout
-                .clone(),
-        },
-        EngineFire,
-    ))
-    .id();
-    let right_truster = commands
-    .spawn((
-        SpriteBundle {
-            transform: Transform::from_xyz(
-                16., -40., 1.,
-            ),
-            texture: space_sheet.sheet.clone(),
-            sprite: Sprite {
-                flip_y: true,
-                ..default()
-            },
-            visibility: Visibility::Hidden,
-            ..default()
-        },
-        TextureAtlas {
-            index: 75,
-            layout: space_sheet
-                .texture_atlas_layout
-                .clone(),
-        },
-        EngineFire,
-    ))
-    .id();
-    let left_truster = commands
-    .spawn((
-        SpriteBundle {
-            transform: Transform::from_xyz(
-                -16., -40., 1.,
-            ),
-            texture: space_sheet.sheet.clone(),
-            sprite: Sprite {
-                flip_y: true,
-                ..default()
-            },
-            visibility: Visibility::Hidden,
-            ..default()
-        },
-        TextureAtlas {
-            index: 75,
-            layout: space_sheet
-                .texture_atlas_layout
-                .clone(),
-        },
-        EngineFire,
-    ))
-    .id();
-    commands.spawn(ShipBundle {
-        sprite_bundle: SpriteBundle {
-            texture: space_sheet.sheet.clone(),
-            ..default()
-        },
-        texture_atlas: TextureAtlas {
-            index: 200,
-            layout: space_sheet
-                    .texture_atlas_layout
-                    .clone(),
-        },
-        player: Player,
-        ship_type: ShipLevels::Initial,
-        collider: Collider::circle(32.),
-        //wrapping_movement: MovementWrapper
-    })
-    .add_child(engine_fire)
-    .add_child(right_truster)
-    .add_child(left_truster);
-    commands.spawn(MeteorBundle::big(
-        Transform::from_xyz(50., 100., 1.),
-        &space_sheet,
+    let Some((space_sheet, station_sheet, level)) = start.get()
+    else {
+        return;
+    };
+    lives.0 = 3;
+    commands.insert_resource(SessionStats::default());
+    commands.insert_resource(CurrentWave::default());
+    commands.insert_resource(Cargo::default());
+    commands.insert_resource(Score::default());
+    commands.insert_resource(Combo::default());
+    clear_color.0 = level.background_color;
+    spawn_level_environment(
+        &mut commands,
+        level,
+        station_sheet,
+        &settings,
+    );
+    commands.insert_resource(settings.ship);
+    spawn_selected_ship(
+        &mut commands,
+        space_sheet,
+        &settings,
+        settings.ship,
+        level.player_start.transform(),
+    );
+    for placement in &level.meteors {
+        let transform = Transform::from_translation(
+            placement.position.extend(1.),
+        );
+        let meteor = MeteorBundle::from_type(
+            placement.meteor_type,
+            transform,
+            space_sheet,
+            &level.meteor_sizes,
+        )
+        .with_speed(placement.speed_multiplier, 1.);
+        match placement.material {
+            Some(material) => {
+                commands.spawn(
+                    meteor.with_material(
+                        material,
+                        space_sheet,
+                        &level.meteor_sizes,
+                    ),
+                )
+            }
+            None => commands.spawn(meteor),
+        };
+    }
+}
+
+fn restore_saved_game(
+    mut commands: Commands,
+    mut start: LevelStart,
+    pending: Res<PendingLoad>,
+    mut settings: ResMut<GameSettings>,
+    mut clear_color: ResMut<ClearColor>,
+    mut lives: ResMut<Lives>,
+) {
+    let Some((space_sheet, station_sheet, level)) = start.get()
+    else {
+        return;
+    };
+    let save = &pending.0;
+
+    // keep flying the saved hull's color, respawns too
+    let saved_color = save
+        .player
+        .as_ref()
+        .and_then(|player| player.ship_color);
+    if let (Some(color), None) = (saved_color, &settings.design) {
+        settings.ship_color = color;
+    }
+
+    clear_color.0 = level.background_color;
+    spawn_level_environment(
+        &mut commands,
+        level,
+        station_sheet,
+        &settings,
+    );
+
+    lives.0 = save.lives;
+    commands.insert_resource(save.cargo.clone());
+    commands.insert_resource(Score(save.score));
+    commands.insert_resource(save.stats.clone());
+    commands.insert_resource(CurrentWave::resume(
+        save.wave,
+        &level.waves,
     ));
-}
\ No newline at end of file
+    commands.insert_resource(Combo::default());
+
+    commands.insert_resource(
+        save.player
+            .as_ref()
+            .map(|player| player.ship_level)
+            .unwrap_or_default(),
+    );
+    if let Some(player) = &save.player {
+        let ship = spawn_selected_ship(
+            &mut commands,
+            space_sheet,
+            &settings,
+            player.ship_level,
+            Transform::from_translation(player.translation)
+                .with_rotation(player.rotation),
+        );
+        commands
+            .entity(ship)
+            .insert(LinearVelocity(player.velocity));
+    } else if save.lives > 0 {
+        // saved while waiting for the next ship
+        commands.insert_resource(RespawnCountdown::default());
+    }
+    for meteor in &save.meteors {
+        commands.spawn(
+            MeteorBundle::from_type(
+                meteor.meteor_type,
+                meteor.transform(),
+                space_sheet,
+                &level.meteor_sizes,
+            )
+            .with_material(
+                meteor.material,
+                space_sheet,
+                &level.meteor_sizes,
+            )
+            .with_movement(meteor.velocity, meteor.spin),
+        );
+    }
+}
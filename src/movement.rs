@@ -1,4 +1,7 @@
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::{
+    ecs::system::SystemParam, prelude::*, window::PrimaryWindow,
+};
+use bevy_xpbd_2d::prelude::*;
 
 use crate::utils::pause_system::Pausable;
 
@@ -12,6 +15,10 @@ impl Plugin for MovementPlugin {
                 .run_if(resource_equals(
                     Pausable::NotPaused,
                 )),
+        )
+        .add_systems(
+            Update,
+            pause_physics.run_if(resource_changed::<Pausable>),
         );
     }
 }
@@ -62,6 +69,26 @@ pub fn rotate_2d(
 #[derive(Component)]
 pub struct MovementWrapper;
 
+/// The part of space on screen, which wrapping objects
+/// stay inside
+#[derive(SystemParam)]
+pub struct PlayArea<'w, 's> {
+    window: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+}
+
+impl PlayArea<'_, '_> {
+    /// Half the window's size, or of a 1280x720 one
+    /// while there is no window
+    pub fn half_size(&self) -> Vec2 {
+        self.window
+            .get_single()
+            .map(|window| {
+                Vec2::new(window.width(), window.height()) / 2.
+            })
+            .unwrap_or(Vec2::new(640., 360.))
+    }
+}
+
 pub fn wrapping_movement(
     mut wrappers: Query<
         &mut Transform,
@@ -91,4 +118,17 @@ pub fn wrapping_movement(
                 window.resolution.height();
         }
     }
-}
\ No newline at end of file
+}
+
+/// Rigid bodies are moved by the physics step rather
+/// than the systems above, so it is paused along with
+/// the game
+fn pause_physics(
+    pausable: Res<Pausable>,
+    mut time: ResMut<Time<Physics>>,
+) {
+    match *pausable {
+        Pausable::Paused => time.pause(),
+        Pausable::NotPaused => time.unpause(),
+    }
+}
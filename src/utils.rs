@@ -1,3 +1,7 @@
 pub mod asset_loader;
+pub mod debug_overlay;
 pub mod kenney_asset;
-pub mod pause_system;
\ No newline at end of file
+pub mod level_asset;
+pub mod pause_system;
+pub mod save_system;
+pub mod screen_shake;
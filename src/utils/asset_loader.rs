@@ -1,9 +1,12 @@
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
 use bevy_asset_loader::prelude::*;
 
 use crate::{
-    utils::kenney_asset::{
-        KenneyAssetPlugin, KenneySpriteSheetAsset,
+    utils::{
+        kenney_asset::{
+            KenneyAssetPlugin, KenneySpriteSheetAsset,
+        },
+        level_asset::{LevelAsset, LevelAssetPlugin},
     },
     GameState,
 };
@@ -12,17 +15,24 @@ pub struct AssetsPlugin;
 
 impl Plugin for AssetsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(KenneyAssetPlugin)
+        app.add_plugins((KenneyAssetPlugin, LevelAssetPlugin))
             .add_loading_state(
                 LoadingState::new(GameState::AssetLoading)
                     .continue_to_state(GameState::Menu)
                     .load_collection::<ImageAssets>()
                     .load_collection::<AudioAssets>()
-                    .load_collection::<FontAssets>(),
+                    .load_collection::<FontAssets>()
+                    .load_collection::<LevelAssets>(),
             );
     }
 }
 
+#[derive(AssetCollection, Resource)]
+pub struct LevelAssets {
+    #[asset(path = "levels/sector1.level.ron")]
+    pub level: Handle<LevelAsset>,
+}
+
 #[derive(AssetCollection, Resource)]
 pub struct FontAssets {
     #[asset(path = "AlfaSlabOne-Regular.ttf")]
@@ -35,6 +45,8 @@ pub struct FontAssets {
 pub struct AudioAssets {
     #[asset(path = "menu_click.ogg")]
     pub menu_click: Handle<AudioSource>,
+    #[asset(path = "gameover.ogg")]
+    pub game_over: Handle<AudioSource>,
 }
 
 #[derive(AssetCollection, Resource)]
@@ -45,8 +57,32 @@ pub struct ImageAssets {
     pub box_checked: Handle<Image>,
     #[asset(path = "glass_panel.png")]
     pub panel_glass: Handle<Image>,
+    #[allow(dead_code)]
     #[asset(path = "pattern_blueprint.png")]
     pub pattern_blueprint: Handle<Image>,
     #[asset(path = "space_sheet.xml")]
     pub space_sheet: Handle<KenneySpriteSheetAsset>,
+    /// Panels, bars and crosshairs for menus and the hud
+    #[asset(path = "uipackSpace_sheet.xml")]
+    pub ui_sheet: Handle<KenneySpriteSheetAsset>,
+    /// Stations, rockets, missiles and effects, at 2X
+    #[asset(path = "spaceShooter2_spritesheet_2X.xml")]
+    pub station_sheet: Handle<KenneySpriteSheetAsset>,
+}
+
+/// The loaded sprite sheets of [`ImageAssets`], each
+/// `None` until it finished loading
+#[derive(SystemParam)]
+pub struct SpriteSheets<'w> {
+    images: Res<'w, ImageAssets>,
+    sheets: Res<'w, Assets<KenneySpriteSheetAsset>>,
+}
+
+impl SpriteSheets<'_> {
+    pub fn space(&self) -> Option<&KenneySpriteSheetAsset> {
+        self.sheets.get(&self.images.space_sheet)
+    }
+    pub fn station(&self) -> Option<&KenneySpriteSheetAsset> {
+        self.sheets.get(&self.images.station_sheet)
+    }
 }
\ No newline at end of file
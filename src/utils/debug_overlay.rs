@@ -0,0 +1,89 @@
+use bevy::{ecs::entity::Entities, prelude::*};
+
+use crate::{
+    entities::weapon::Projectile,
+    utils::asset_loader::FontAssets,
+    GameState, InGame,
+};
+
+/// F3 shows entity counts in the corner, to check nothing
+/// piles up over long sessions
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnExit(GameState::AssetLoading),
+            spawn_entity_counter,
+        )
+        .add_systems(
+            Update,
+            (toggle_entity_counter, count_entities)
+                .chain()
+                .run_if(not(in_state(GameState::AssetLoading))),
+        );
+    }
+}
+
+#[derive(Component)]
+struct EntityCounter;
+
+fn spawn_entity_counter(
+    mut commands: Commands,
+    fonts: Res<FontAssets>,
+) {
+    commands.spawn((
+        TextBundle {
+            visibility: Visibility::Hidden,
+            ..TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: fonts.roboto.clone(),
+                    font_size: 16.0,
+                    color: Color::YELLOW,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.),
+                top: Val::Px(10.),
+                ..default()
+            })
+        },
+        EntityCounter,
+    ));
+}
+
+fn toggle_entity_counter(
+    input: Res<ButtonInput<KeyCode>>,
+    mut counters: Query<&mut Visibility, With<EntityCounter>>,
+) {
+    if !input.just_pressed(KeyCode::F3) {
+        return;
+    }
+    for mut visibility in &mut counters {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn count_entities(
+    entities: &Entities,
+    in_game: Query<(), With<InGame>>,
+    projectiles: Query<(), With<Projectile>>,
+    mut counters: Query<(&mut Text, &Visibility), With<EntityCounter>>,
+) {
+    for (mut text, visibility) in &mut counters {
+        if visibility == Visibility::Hidden {
+            continue;
+        }
+        text.sections[0].value = format!(
+            "Entities: {}\nIn game: {}\nProjectiles: {}",
+            entities.len(),
+            in_game.iter().count(),
+            projectiles.iter().count(),
+        );
+    }
+}
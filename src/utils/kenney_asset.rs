@@ -1,14 +1,21 @@
 // This plugin taken from https://github.com/rust-adventure/asteroids/blob/yt-2024-04-01/src/kenney_assets.rs Thanks to https://github.com/ChristopherBiscardi
 
+use std::{
+    num::ParseIntError,
+    path::{Path, PathBuf},
+};
+
 use bevy::{asset::LoadedAsset, utils::thiserror};
 use bevy::{
     asset::{
         io::Reader, AssetLoader, AsyncReadExt, LoadContext,
+        LoadDirectError,
     },
     prelude::*,
     reflect::TypePath,
-    utils::BoxedFuture,
+    utils::{BoxedFuture, HashMap},
 };
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 pub struct KenneyAssetPlugin;
@@ -35,115 +42,319 @@ pub struct KenneySpriteSheetAsset {
     pub textures: Vec<SubTexture>,
     pub sheet: Handle<Image>,
     pub texture_atlas_layout: Handle<TextureAtlasLayout>,
+    /// SubTexture name to its index in `textures` and the
+    /// atlas layout
+    pub indices: HashMap<String, usize>,
+    /// Multiply sprite sizes by this to show them at the
+    /// intended size, see [`KenneySpriteSheetSettings::scale`]
+    pub scale: f32,
+}
+
+/// Returned when asking a [`KenneySpriteSheetAsset`] for a
+/// SubTexture name it does not contain
+#[derive(Debug, Error)]
+#[error("No SubTexture named {name:?} in sprite sheet")]
+pub struct UnknownSubTexture {
+    pub name: String,
+}
+
+impl KenneySpriteSheetAsset {
+    /// Index of the SubTexture called `name` (including
+    /// its `.png` extension, as written in the XML)
+    pub fn index_of(
+        &self,
+        name: &str,
+    ) -> Result<usize, UnknownSubTexture> {
+        self.indices.get(name).copied().ok_or_else(|| {
+            UnknownSubTexture {
+                name: name.to_string(),
+            }
+        })
+    }
+
+    /// Size in pixels of the SubTexture called `name`
+    pub fn size_of(
+        &self,
+        name: &str,
+    ) -> Result<Vec2, UnknownSubTexture> {
+        let texture = &self.textures[self.index_of(name)?];
+        Ok(Vec2::new(texture.width as f32, texture.height as f32))
+    }
+
+    /// Indices of the numbered SubTextures `prefix0.png`,
+    /// `prefix1.png`, ... in frame order, e.g. `"fire"`
+    /// gives `fire00.png` to `fire19.png` but not
+    /// `fire_big.png`
+    pub fn frames(
+        &self,
+        prefix: &str,
+    ) -> Result<Vec<usize>, UnknownSubTexture> {
+        let mut frames: Vec<(u32, usize)> = self
+            .indices
+            .iter()
+            .filter_map(|(name, index)| {
+                let number = name
+                    .strip_prefix(prefix)?
+                    .strip_suffix(".png")?;
+                if number.is_empty()
+                    || !number.chars().all(|c| c.is_ascii_digit())
+                {
+                    return None;
+                }
+                Some((number.parse().ok()?, *index))
+            })
+            .collect();
+        if frames.is_empty() {
+            return Err(UnknownSubTexture {
+                name: format!("{prefix}*.png"),
+            });
+        }
+        frames.sort_unstable();
+        Ok(frames.into_iter().map(|(_, index)| index).collect())
+    }
+
+    /// A [`TextureAtlas`] showing the SubTexture called
+    /// `name`
+    ///
+    /// ```ignore
+    /// let atlas = space_sheet.atlas("playerShip1_blue.png")?;
+    /// ```
+    pub fn atlas(
+        &self,
+        name: &str,
+    ) -> Result<TextureAtlas, UnknownSubTexture> {
+        Ok(TextureAtlas {
+            index: self.index_of(name)?,
+            layout: self.texture_atlas_layout.clone(),
+        })
+    }
 }
 
 #[derive(Default)]
 pub struct KenneySpriteSheetAssetLoader;
 
+/// Per-sheet loader settings, set them in the XML's
+/// `.meta` file:
+///
+/// ```ron
+/// (
+///     meta_format_version: "1.0",
+///     asset: Load(
+///         loader: "space_miner::utils::kenney_asset::KenneySpriteSheetAssetLoader",
+///         settings: (scale: 0.5, inset: 1),
+///     ),
+/// )
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KenneySpriteSheetSettings {
+    /// Image to use, relative to the asset folder. Wins
+    /// over the XML `imagePath` attribute.
+    pub image_path: Option<PathBuf>,
+    /// Load the image named by the XML `imagePath`
+    /// attribute, next to the XML. When off, or when the
+    /// attribute is missing, the PNG with the XML's stem
+    /// is loaded instead.
+    pub use_xml_image_path: bool,
+    /// Stored on the asset for sprites to be scaled by,
+    /// e.g. `0.5` for a 2X sheet
+    pub scale: f32,
+    /// Pixels trimmed off every side of each frame, to
+    /// avoid neighbouring frames bleeding in when filtered
+    pub inset: u32,
+}
+
+impl Default for KenneySpriteSheetSettings {
+    fn default() -> Self {
+        KenneySpriteSheetSettings {
+            image_path: None,
+            use_xml_image_path: true,
+            scale: 1.,
+            inset: 0,
+        }
+    }
+}
+
 /// Possible errors that can be produced by [`KenneySpriteSheetAssetLoader`]
 #[non_exhaustive]
 #[derive(Debug, Error)]
 pub enum KenneySpriteSheetAssetLoaderError {
-    /// An [IO](std::io) Error
+    /// An [IO](std::io) Error, this includes XML that is
+    /// not valid UTF-8
     #[error("Could not load asset: {0}")]
     Io(#[from] std::io::Error),
+    /// The sheet image next to the XML could not be loaded
+    #[error("Could not load sprite sheet image: {0}")]
+    ImageLoad(#[from] Box<LoadDirectError>),
+    /// The file next to the XML loaded as something other
+    /// than an [`Image`]
+    #[error("Sprite sheet {0:?} is not an image")]
+    NotAnImage(PathBuf),
+    /// A [roxmltree] Error
+    #[error("Could not parse XML: {0}")]
+    Xml(#[from] roxmltree::Error),
+    /// A SubTexture lacks one of its attributes
+    #[error("SubTexture {name:?} has no {attr:?} attribute")]
+    MissingAttribute { name: String, attr: &'static str },
+    /// A SubTexture coordinate is not a whole number
+    #[error("SubTexture {name:?} has an invalid {attr:?}: {source}")]
+    ParseInt {
+        name: String,
+        attr: &'static str,
+        source: ParseIntError,
+    },
+    /// A SubTexture reaches past the edge of the image
+    #[error("SubTexture {name:?} exceeds the {width}x{height} sheet")]
+    OutOfBounds {
+        name: String,
+        width: u32,
+        height: u32,
+    },
+}
+
+/// Reads one [`SubTexture`] element, checking it against
+/// the `sheet_size` of the image it points into
+fn parse_sub_texture(
+    node: roxmltree::Node,
+    sheet_size: UVec2,
+) -> Result<SubTexture, KenneySpriteSheetAssetLoaderError> {
+    let name = node
+        .attribute("name")
+        .ok_or_else(|| {
+            KenneySpriteSheetAssetLoaderError::MissingAttribute {
+                name: "<unnamed>".to_string(),
+                attr: "name",
+            }
+        })?
+        .to_string();
+    let number = |attr: &'static str| {
+        node.attribute(attr)
+            .ok_or_else(|| {
+                KenneySpriteSheetAssetLoaderError::MissingAttribute {
+                    name: name.clone(),
+                    attr,
+                }
+            })?
+            .parse::<u32>()
+            .map_err(|source| {
+                KenneySpriteSheetAssetLoaderError::ParseInt {
+                    name: name.clone(),
+                    attr,
+                    source,
+                }
+            })
+    };
+    let x = number("x")?;
+    let y = number("y")?;
+    let width = number("width")?;
+    let height = number("height")?;
+
+    let fits = |start: u32, length: u32, limit: u32| {
+        start
+            .checked_add(length)
+            .is_some_and(|end| end <= limit)
+    };
+    if !fits(x, width, sheet_size.x)
+        || !fits(y, height, sheet_size.y)
+    {
+        return Err(KenneySpriteSheetAssetLoaderError::OutOfBounds {
+            name,
+            width: sheet_size.x,
+            height: sheet_size.y,
+        });
+    }
+    Ok(SubTexture {
+        name,
+        x,
+        y,
+        width,
+        height,
+    })
 }
 
 impl AssetLoader for KenneySpriteSheetAssetLoader {
     type Asset = KenneySpriteSheetAsset;
-    type Settings = ();
+    type Settings = KenneySpriteSheetSettings;
     type Error = KenneySpriteSheetAssetLoaderError;
     fn load<'a>(
         &'a self,
         reader: &'a mut Reader,
-        _settings: &'a (),
+        settings: &'a KenneySpriteSheetSettings,
         load_context: &'a mut LoadContext,
     ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>>
     {
         Box::pin(async move {
+            let mut xml_string = String::new();
+            reader.read_to_string(&mut xml_string).await?;
+
+            let doc = roxmltree::Document::parse(&xml_string)?;
+
             // original path must be the xml file
             let original_path =
-                load_context.asset_path().path();
-            let image_path =
-                original_path.with_extension("png");
+                load_context.asset_path().path().to_owned();
+            let xml_image_path = doc
+                .root_element()
+                .attribute("imagePath")
+                .filter(|_| settings.use_xml_image_path);
+
+            let image_path = match (
+                &settings.image_path,
+                xml_image_path,
+            ) {
+                (Some(image_path), _) => image_path.clone(),
+                (None, Some(image_path)) => original_path
+                    .parent()
+                    .unwrap_or(Path::new(""))
+                    .join(image_path),
+                (None, None) => {
+                    if settings.use_xml_image_path {
+                        warn!(
+                            "{original_path:?} has no imagePath, loading the PNG named after it"
+                        );
+                    }
+                    original_path.with_extension("png")
+                }
+            };
             let image = load_context
                 .load_direct(image_path.clone())
                 .await
-                // todo: .unwrap
-                .unwrap();
+                .map_err(Box::new)?;
+            let spritesheet_size = image
+                .get::<Image>()
+                .ok_or_else(|| {
+                    KenneySpriteSheetAssetLoaderError::NotAnImage(
+                        image_path.clone(),
+                    )
+                })?
+                .size();
             let sheet_handle: Handle<Image> =
                 load_context.load(image_path);
 
-            let spritesheet_image: &Image =
-                image.get().unwrap();
-            let spritesheet_size =
-                spritesheet_image.size_f32();
-
-            let mut xml_string = String::new();
-            reader
-                .read_to_string(&mut xml_string)
-                .await
-                .unwrap();
-
-            let doc =
-                roxmltree::Document::parse(&xml_string)
-                    .unwrap();
-
-            let space_sheet_dimensions = Vec2::new(
-                spritesheet_size.x,
-                spritesheet_size.y,
-            );
-
             let mut layout = TextureAtlasLayout::new_empty(
-                space_sheet_dimensions,
+                spritesheet_size.as_vec2(),
             );
-            let sub_textures: Vec<SubTexture> = doc
+            let sub_textures = doc
                 .descendants()
                 .filter(|element| {
                     element.tag_name()
                         == "SubTexture".into()
                 })
-                .map(|tex| {
-                    let x: u32 = tex
-                        .attribute("x")
-                        .unwrap()
-                        .parse()
-                        .unwrap();
-                    let y: u32 = tex
-                        .attribute("y")
-                        .unwrap()
-                        .parse()
-                        .unwrap();
-                    let width: u32 = tex
-                        .attribute("width")
-                        .unwrap()
-                        .parse()
-                        .unwrap();
-                    let height: u32 = tex
-                        .attribute("height")
-                        .unwrap()
-                        .parse()
-                        .unwrap();
-
-                    layout.add_texture(Rect::from_corners(
-                        Vec2::new(x as f32, y as f32),
-                        Vec2::new(
-                            (x + width) as f32,
-                            (y + height) as f32,
-                        ),
-                    ));
-                    SubTexture {
-                        name: tex
-                            .attribute("name")
-                            .unwrap()
-                            .to_string(),
-                        x,
-                        y,
-                        width,
-                        height,
-                    }
+                .map(|node| {
+                    parse_sub_texture(node, spritesheet_size)
                 })
-                .collect();
+                .collect::<Result<Vec<_>, _>>()?;
+            for texture in &sub_textures {
+                let min = UVec2::new(texture.x, texture.y);
+                let size =
+                    UVec2::new(texture.width, texture.height);
+                // never inset a frame down to nothing
+                let inset = UVec2::splat(settings.inset)
+                    .min((size.saturating_sub(UVec2::ONE)) / 2);
+                layout.add_texture(Rect::from_corners(
+                    (min + inset).as_vec2(),
+                    (min + size - inset).as_vec2(),
+                ));
+            }
             let texture_atlas_layout =
                 LoadedAsset::from(layout);
             let layout_handle = load_context
@@ -151,7 +362,16 @@ impl AssetLoader for KenneySpriteSheetAssetLoader {
                     "texture_atlas_layout",
                     texture_atlas_layout,
                 );
+            let indices = sub_textures
+                .iter()
+                .enumerate()
+                .map(|(index, texture)| {
+                    (texture.name.clone(), index)
+                })
+                .collect();
             Ok(KenneySpriteSheetAsset {
+                indices,
+                scale: settings.scale,
                 textures: sub_textures,
                 sheet: sheet_handle,
                 texture_atlas_layout: layout_handle,
@@ -162,4 +382,92 @@ impl AssetLoader for KenneySpriteSheetAssetLoader {
     fn extensions(&self) -> &[&str] {
         &["xml"]
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(
+        element: &str,
+        sheet_size: UVec2,
+    ) -> Result<SubTexture, KenneySpriteSheetAssetLoaderError> {
+        let doc = roxmltree::Document::parse(element).unwrap();
+        parse_sub_texture(doc.root_element(), sheet_size)
+    }
+
+    #[test]
+    fn parses_a_sub_texture() {
+        let texture = parse(
+            r#"<SubTexture name="beam0.png" x="143" y="377" width="43" height="31"/>"#,
+            UVec2::splat(1024),
+        )
+        .unwrap();
+        assert_eq!(texture.name, "beam0.png");
+        assert_eq!(
+            (texture.x, texture.y, texture.width, texture.height),
+            (143, 377, 43, 31)
+        );
+    }
+
+    #[test]
+    fn reports_missing_attributes() {
+        let error = parse(
+            r#"<SubTexture name="beam0.png" x="143" y="377" width="43"/>"#,
+            UVec2::splat(1024),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            KenneySpriteSheetAssetLoaderError::MissingAttribute {
+                attr: "height",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn reports_invalid_numbers() {
+        let error = parse(
+            r#"<SubTexture name="beam0.png" x="-1" y="377" width="43" height="31"/>"#,
+            UVec2::splat(1024),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            KenneySpriteSheetAssetLoaderError::ParseInt { attr: "x", .. }
+        ));
+    }
+
+    #[test]
+    fn rejects_sub_textures_past_the_sheet() {
+        for element in [
+            r#"<SubTexture name="a.png" x="1000" y="0" width="43" height="31"/>"#,
+            // would overflow when added up
+            r#"<SubTexture name="a.png" x="4294967295" y="0" width="2" height="2"/>"#,
+        ] {
+            assert!(matches!(
+                parse(element, UVec2::splat(1024)),
+                Err(KenneySpriteSheetAssetLoaderError::OutOfBounds { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn parses_the_bundled_sheets() {
+        for path in [
+            "assets/space_sheet.xml",
+            "assets/uipackSpace_sheet.xml",
+            "assets/spaceShooter2_spritesheet_2X.xml",
+        ] {
+            let xml = std::fs::read_to_string(path).unwrap();
+            let doc = roxmltree::Document::parse(&xml).unwrap();
+            let textures = doc
+                .descendants()
+                .filter(|node| node.tag_name() == "SubTexture".into())
+                .map(|node| parse_sub_texture(node, UVec2::splat(4096)))
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert!(!textures.is_empty(), "{path} has no SubTextures");
+        }
+    }
+}
@@ -0,0 +1,278 @@
+use bevy::{
+    asset::{
+        io::Reader, AssetLoader, AsyncReadExt, LoadContext,
+    },
+    prelude::*,
+    ecs::system::SystemParam,
+    reflect::TypePath,
+    utils::{thiserror, BoxedFuture},
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    entities::{
+        meteor::{MeteorMaterial, MeteorSizes, MeteorType},
+        station::StationBundle,
+        waves::WaveTable,
+    },
+    gameui::settings::{AudioSettings, GameSettings},
+    utils::{
+        asset_loader::LevelAssets,
+        kenney_asset::KenneySpriteSheetAsset,
+    },
+    GameState, InGame,
+};
+
+pub struct LevelAssetPlugin;
+
+impl Plugin for LevelAssetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LevelAsset>()
+            .init_asset_loader::<LevelAssetLoader>()
+            .add_systems(
+                Update,
+                apply_reloaded_background
+                    .run_if(not(in_state(GameState::AssetLoading))),
+            );
+    }
+}
+
+/// Everything needed to set up a play session: where
+/// the player starts, the opening meteor field, the wave
+/// script, background and music.
+#[derive(Asset, TypePath, Debug)]
+pub struct LevelAsset {
+    pub name: String,
+    pub player_start: PlayerStart,
+    pub meteors: Vec<MeteorPlacement>,
+    pub meteor_sizes: MeteorSizes,
+    pub stations: Vec<StationPlacement>,
+    pub waves: WaveTable,
+    pub background_color: Color,
+    #[dependency]
+    pub background_image: Option<Handle<Image>>,
+    #[dependency]
+    pub music: Option<Handle<AudioSource>>,
+}
+
+/// The level being played, `None` until it finished
+/// loading
+#[derive(SystemParam)]
+pub struct CurrentLevel<'w> {
+    levels: Res<'w, LevelAssets>,
+    level_assets: Res<'w, Assets<LevelAsset>>,
+}
+
+impl CurrentLevel<'_> {
+    pub fn get(&self) -> Option<&LevelAsset> {
+        self.level_assets.get(&self.levels.level)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlayerStart {
+    pub position: Vec2,
+    /// Radians, counter-clockwise from facing up
+    #[serde(default)]
+    pub rotation: f32,
+}
+
+impl PlayerStart {
+    pub fn transform(&self) -> Transform {
+        Transform::from_translation(self.position.extend(0.))
+            .with_rotation(Quat::from_rotation_z(self.rotation))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MeteorPlacement {
+    pub meteor_type: MeteorType,
+    pub position: Vec2,
+    /// Rolled randomly when left out
+    #[serde(default)]
+    pub material: Option<MeteorMaterial>,
+    #[serde(default = "one")]
+    pub speed_multiplier: f32,
+}
+
+/// Scenery from the space shooter sheet
+#[derive(Debug, Clone, Deserialize)]
+pub struct StationPlacement {
+    /// SubTexture name, e.g. `"spaceStation_017.png"`
+    pub sprite: String,
+    pub position: Vec2,
+    #[serde(default)]
+    pub rotation: f32,
+}
+
+fn one() -> f32 {
+    1.
+}
+
+/// The on-disk shape of a `.level.ron` file, asset paths
+/// are resolved into handles by [`LevelAssetLoader`]
+#[derive(Deserialize)]
+struct LevelDescription {
+    name: String,
+    player_start: PlayerStart,
+    #[serde(default)]
+    meteors: Vec<MeteorPlacement>,
+    #[serde(default)]
+    meteor_sizes: MeteorSizes,
+    #[serde(default)]
+    stations: Vec<StationPlacement>,
+    waves: WaveTable,
+    background: BackgroundDescription,
+    #[serde(default)]
+    music: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BackgroundDescription {
+    color: (f32, f32, f32),
+    #[serde(default)]
+    image: Option<String>,
+}
+
+#[derive(Default)]
+pub struct LevelAssetLoader;
+
+/// Possible errors that can be produced by [`LevelAssetLoader`]
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum LevelAssetLoaderError {
+    /// An [IO](std::io) Error
+    #[error("Could not load asset: {0}")]
+    Io(#[from] std::io::Error),
+    /// A [RON](ron) Error
+    #[error("Could not parse RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for LevelAssetLoader {
+    type Asset = LevelAsset;
+    type Settings = ();
+    type Error = LevelAssetLoaderError;
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>>
+    {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let description: LevelDescription =
+                ron::de::from_bytes(&bytes)?;
+
+            let (r, g, b) = description.background.color;
+            Ok(LevelAsset {
+                name: description.name,
+                player_start: description.player_start,
+                meteors: description.meteors,
+                meteor_sizes: description.meteor_sizes,
+                stations: description.stations,
+                waves: description.waves,
+                background_color: Color::rgb(r, g, b),
+                background_image: description
+                    .background
+                    .image
+                    .map(|path| load_context.load(path)),
+                music: description
+                    .music
+                    .map(|path| load_context.load(path)),
+            })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+/// Size of the tiled background behind the play area
+const BACKGROUND_SIZE: f32 = 4096.;
+
+/// Spawns the level's background image, stations and
+/// music for a play session. All are torn down with the
+/// rest of the session.
+pub fn spawn_level_environment(
+    commands: &mut Commands,
+    level: &LevelAsset,
+    station_sheet: &KenneySpriteSheetAsset,
+    settings: &GameSettings,
+) {
+    if let Some(image) = &level.background_image {
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_xyz(0., 0., -10.),
+                texture: image.clone(),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::splat(
+                        BACKGROUND_SIZE,
+                    )),
+                    ..default()
+                },
+                ..default()
+            },
+            ImageScaleMode::Tiled {
+                tile_x: true,
+                tile_y: true,
+                stretch_value: 1.,
+            },
+            InGame,
+        ));
+    }
+    for station in &level.stations {
+        let Some(bundle) = StationBundle::new(
+            &station.sprite,
+            Transform::from_translation(
+                station.position.extend(-5.),
+            )
+            .with_rotation(Quat::from_rotation_z(
+                station.rotation,
+            )),
+            station_sheet,
+        ) else {
+            warn!(
+                "Skipping station, {} is not on the station sheet",
+                station.sprite
+            );
+            continue;
+        };
+        commands.spawn(bundle);
+    }
+    if let Some(music) = &level.music {
+        if settings.audio == AudioSettings::On {
+            commands.spawn((
+                AudioBundle {
+                    source: music.clone(),
+                    settings: PlaybackSettings::LOOP,
+                },
+                InGame,
+            ));
+        }
+    }
+}
+
+/// Keeps the clear color in sync when the level file is
+/// edited while the game runs (with the `hot_reload`
+/// feature)
+fn apply_reloaded_background(
+    mut events: EventReader<AssetEvent<LevelAsset>>,
+    levels: Res<LevelAssets>,
+    level_assets: Res<Assets<LevelAsset>>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    for event in events.read() {
+        if !event.is_modified(&levels.level) {
+            continue;
+        }
+        if let Some(level) = level_assets.get(&levels.level) {
+            info!("Reloaded level {}", level.name);
+            clear_color.0 = level.background_color;
+        }
+    }
+}
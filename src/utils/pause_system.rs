@@ -2,6 +2,7 @@
 use bevy::prelude::*;
 
 use crate::{
+    gameui::button::SpawnButton,
     utils::asset_loader::ImageAssets,
     GameState
 }; 
@@ -61,6 +62,7 @@ fn handle_pause_toggle(
 /// ```rust
 /// app.add_systems(my_system.run_if(not(paused)));
 /// ```
+#[allow(dead_code)]
 pub fn paused() -> impl Condition<()> {
     IntoSystem::into_system(|paused: Res<Pausable>| {
         *paused == Pausable::Paused
@@ -68,11 +70,13 @@ pub fn paused() -> impl Condition<()> {
 }
 
 /// A one-shot system to pause the game
+#[allow(dead_code)]
 pub fn pause(mut pausable: ResMut<Pausable>) {
     *pausable = Pausable::Paused;
 }
 
 /// A one-shot system to unpause the game
+#[allow(dead_code)]
 pub fn unpause(mut pausable: ResMut<Pausable>) {
     *pausable = Pausable::NotPaused;
 }
@@ -98,6 +102,10 @@ fn show_pause_menu(
         .spawn((
             ImageBundle {
                 style: Style {
+                    width: Val::Px(300.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(20.),
                     padding: UiRect::all(Val::Px(20.)),
                     ..default()
                 },
@@ -120,6 +128,10 @@ fn show_pause_menu(
             );
         })
         .id();
+    commands.add(SpawnButton {
+        parent: pause_text,
+        text: "Save",
+    });
 
     let pause_menu_id = commands
         .spawn(NodeBundle {
@@ -0,0 +1,521 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{
+    ecs::system::{Command, RunSystemOnce, SystemParam},
+    prelude::*,
+    utils::thiserror,
+};
+use bevy_xpbd_2d::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    entities::{
+        lives::Lives,
+        meteor::{Meteor, MeteorMaterial, MeteorType},
+        ore::Cargo,
+        score::Score,
+        waves::CurrentWave,
+        spaceship::{ShipColor, ShipLevels},
+        ship_builder::ShipDesign,
+    },
+    gameui::game_over::SessionStats,
+    GameState, InGame, Player,
+};
+
+/// Number of save slots offered on the Save page
+pub const SAVE_SLOTS: usize = 3;
+
+const SAVE_DIRECTORY: &str = "saves";
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveSaveSlot>()
+            .add_systems(
+                Update,
+                (quick_save, fade_save_notice)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                OnExit(GameState::Playing),
+                clear_pending_load,
+            );
+    }
+}
+
+/// The slot the current session was started from or last
+/// saved to, and the one [`SaveGame`] writes to.
+#[derive(Resource, Default, Debug)]
+pub struct ActiveSaveSlot(pub Option<usize>);
+
+/// Session read from disk when a non-empty slot was
+/// chosen. Its presence makes entering
+/// [`GameState::Playing`] restore this state instead of
+/// starting a fresh game.
+#[derive(Resource, Debug)]
+pub struct PendingLoad(pub SaveData);
+
+/// Possible errors when reading or writing a save slot
+#[derive(Debug, Error)]
+pub enum SaveError {
+    #[error("Could not access save file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not serialize save: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("Could not parse save file: {0}")]
+    Deserialize(#[from] ron::error::SpannedError),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveData {
+    /// Seconds since the unix epoch
+    pub saved_at: u64,
+    pub lives: usize,
+    pub player: Option<SavedPlayer>,
+    pub meteors: Vec<SavedMeteor>,
+    #[serde(default)]
+    pub cargo: Cargo,
+    #[serde(default)]
+    pub score: u32,
+    #[serde(default)]
+    pub stats: SessionStats,
+    #[serde(default)]
+    pub wave: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPlayer {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub ship_level: ShipLevels,
+    /// Missing from saves made before colors were stored,
+    /// those keep the hangar's color
+    #[serde(default)]
+    pub ship_color: Option<ShipColor>,
+    /// Pixels per second
+    #[serde(default)]
+    pub velocity: Vec2,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedMeteor {
+    pub meteor_type: MeteorType,
+    #[serde(default)]
+    pub material: MeteorMaterial,
+    pub translation: Vec3,
+    pub rotation: Quat,
+    /// Pixels per second
+    #[serde(default)]
+    pub velocity: Vec2,
+    /// Radians per second
+    #[serde(default, alias = "spin_speed")]
+    pub spin: f32,
+}
+
+impl SavedMeteor {
+    pub fn transform(&self) -> Transform {
+        Transform::from_translation(self.translation)
+            .with_rotation(self.rotation)
+    }
+}
+
+fn slot_path(slot: usize) -> PathBuf {
+    PathBuf::from(SAVE_DIRECTORY)
+        .join(format!("slot{}.ron", slot + 1))
+}
+
+/// Reads a slot from disk. `Ok(None)` means the slot is
+/// empty.
+pub fn read_slot(
+    slot: usize,
+) -> Result<Option<SaveData>, SaveError> {
+    let path = slot_path(slot);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(path)?;
+    Ok(Some(ron::from_str(&contents)?))
+}
+
+pub fn write_slot(
+    slot: usize,
+    data: &SaveData,
+) -> Result<(), SaveError> {
+    fs::create_dir_all(SAVE_DIRECTORY)?;
+    let contents = ron::ser::to_string_pretty(
+        data,
+        ron::ser::PrettyConfig::default(),
+    )?;
+    fs::write(slot_path(slot), contents)?;
+    Ok(())
+}
+
+pub fn delete_slot(slot: usize) -> Result<(), SaveError> {
+    let path = slot_path(slot);
+    if path.exists() {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn high_score_path() -> PathBuf {
+    PathBuf::from(SAVE_DIRECTORY).join("highscore.ron")
+}
+
+/// Reads the best score ever reached, `0` if none was
+/// recorded yet
+pub fn read_high_score() -> Result<u32, SaveError> {
+    let path = high_score_path();
+    if !path.exists() {
+        return Ok(0);
+    }
+    let contents = fs::read_to_string(path)?;
+    Ok(ron::from_str(&contents)?)
+}
+
+pub fn write_high_score(score: u32) -> Result<(), SaveError> {
+    fs::create_dir_all(SAVE_DIRECTORY)?;
+    fs::write(high_score_path(), ron::to_string(&score)?)?;
+    Ok(())
+}
+
+fn unlocks_path() -> PathBuf {
+    PathBuf::from(SAVE_DIRECTORY).join("unlocks.ron")
+}
+
+/// Reads the best hull ever upgraded to, the first one
+/// if none was recorded yet
+pub fn read_unlocked_hull() -> Result<ShipLevels, SaveError> {
+    let path = unlocks_path();
+    if !path.exists() {
+        return Ok(ShipLevels::default());
+    }
+    let contents = fs::read_to_string(path)?;
+    Ok(ron::from_str(&contents)?)
+}
+
+pub fn write_unlocked_hull(
+    hull: ShipLevels,
+) -> Result<(), SaveError> {
+    fs::create_dir_all(SAVE_DIRECTORY)?;
+    fs::write(unlocks_path(), ron::to_string(&hull)?)?;
+    Ok(())
+}
+
+fn designs_path() -> PathBuf {
+    PathBuf::from(SAVE_DIRECTORY).join("designs.ron")
+}
+
+/// Reads the ship designs saved from the shipyard,
+/// skipping any that pick parts the sheet does not have
+pub fn read_designs() -> Result<Vec<ShipDesign>, SaveError> {
+    let path = designs_path();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents = fs::read_to_string(path)?;
+    let designs: Vec<ShipDesign> = ron::from_str(&contents)?;
+    Ok(designs
+        .into_iter()
+        .filter(|design| {
+            let valid = design.is_valid();
+            if !valid {
+                warn!(
+                    "Skipping ship design {:?} with unknown parts",
+                    design.name
+                );
+            }
+            valid
+        })
+        .collect())
+}
+
+pub fn write_designs(
+    designs: &[ShipDesign],
+) -> Result<(), SaveError> {
+    fs::create_dir_all(SAVE_DIRECTORY)?;
+    let contents = ron::ser::to_string_pretty(
+        designs,
+        ron::ser::PrettyConfig::default(),
+    )?;
+    fs::write(designs_path(), contents)?;
+    Ok(())
+}
+
+/// Formats [`SaveData::saved_at`] as a UTC
+/// `YYYY-MM-DD HH:MM` string.
+pub fn format_timestamp(seconds: u64) -> String {
+    let days = (seconds / 86_400) as i64;
+    let minutes_of_day = (seconds % 86_400) / 60;
+
+    // civil-from-days, see
+    // http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460
+        + day_of_era / 36_524
+        - day_of_era / 146_096)
+        / 365;
+    let day_of_year = day_of_era
+        - (365 * year_of_era + year_of_era / 4
+            - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year =
+        year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        minutes_of_day / 60,
+        minutes_of_day % 60
+    )
+}
+
+/// A [Command] that selects the slot a new session is
+/// started from. A slot that holds a save is queued for
+/// restoring, an empty (or unreadable) one starts a fresh
+/// game.
+pub struct SelectSaveSlot(pub usize);
+
+impl Command for SelectSaveSlot {
+    fn apply(self, world: &mut World) {
+        world.insert_resource(ActiveSaveSlot(Some(self.0)));
+        match read_slot(self.0) {
+            Ok(Some(data)) => {
+                world.insert_resource(PendingLoad(data));
+            }
+            Ok(None) => {
+                world.remove_resource::<PendingLoad>();
+            }
+            Err(error) => {
+                error!(
+                    "Could not load save slot {}: {error}",
+                    self.0 + 1
+                );
+                world.remove_resource::<PendingLoad>();
+            }
+        }
+    }
+}
+
+fn clear_pending_load(mut commands: Commands) {
+    commands.remove_resource::<PendingLoad>();
+}
+
+/// The run-wide resources written into a save
+#[derive(SystemParam)]
+struct RunProgress<'w> {
+    lives: Res<'w, Lives>,
+    cargo: Res<'w, Cargo>,
+    score: Res<'w, Score>,
+    stats: Res<'w, SessionStats>,
+    wave: Res<'w, CurrentWave>,
+}
+
+/// A [Command] that writes the running session to the
+/// active slot, or to the first empty one when there is
+/// none, and tells the player how it went.
+pub struct SaveGame;
+
+impl Command for SaveGame {
+    fn apply(self, world: &mut World) {
+        world.run_system_once(save_game);
+    }
+}
+
+/// `F5` saves without going through the pause menu
+fn quick_save(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F5) {
+        commands.add(SaveGame);
+    }
+}
+
+fn save_game(
+    mut commands: Commands,
+    mut active_slot: ResMut<ActiveSaveSlot>,
+    progress: RunProgress,
+    player: Query<
+        (&Transform, &ShipLevels, &ShipColor, &LinearVelocity),
+        With<Player>,
+    >,
+    meteors: Query<
+        (
+            &Transform,
+            &MeteorType,
+            &MeteorMaterial,
+            &LinearVelocity,
+            &AngularVelocity,
+        ),
+        With<Meteor>,
+    >,
+    notices: Query<Entity, With<SaveNotice>>,
+) {
+    for entity in &notices {
+        commands.entity(entity).despawn_recursive();
+    }
+    let free_slot = || {
+        (0..SAVE_SLOTS)
+            .find(|slot| matches!(read_slot(*slot), Ok(None)))
+    };
+    let Some(slot) = active_slot.0.or_else(free_slot) else {
+        warn!("No save slot selected and none is free, not saving");
+        show_save_notice(&mut commands, "No free save slot");
+        return;
+    };
+
+    let saved_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+
+    let RunProgress {
+        lives,
+        cargo,
+        score,
+        stats,
+        wave,
+    } = progress;
+    let data = SaveData {
+        saved_at,
+        lives: lives.0,
+        player: player.get_single().ok().map(
+            |(transform, ship_level, ship_color, velocity)| SavedPlayer {
+                translation: transform.translation,
+                rotation: transform.rotation,
+                ship_level: *ship_level,
+                ship_color: Some(*ship_color),
+                velocity: velocity.0,
+            },
+        ),
+        meteors: meteors
+            .iter()
+            .map(
+                |(
+                    transform,
+                    meteor_type,
+                    material,
+                    velocity,
+                    spin,
+                )| SavedMeteor {
+                    meteor_type: *meteor_type,
+                    material: *material,
+                    translation: transform.translation,
+                    rotation: transform.rotation,
+                    velocity: velocity.0,
+                    spin: spin.0,
+                },
+            )
+            .collect(),
+        cargo: cargo.clone(),
+        score: score.0,
+        stats: stats.clone(),
+        wave: wave.number,
+    };
+
+    let message = match write_slot(slot, &data) {
+        Ok(()) => {
+            info!("Saved game to slot {}", slot + 1);
+            active_slot.0 = Some(slot);
+            format!("Saved to slot {}", slot + 1)
+        }
+        Err(error) => {
+            error!("Could not save to slot {}: {error}", slot + 1);
+            format!("Could not save to slot {}", slot + 1)
+        }
+    };
+    show_save_notice(&mut commands, message);
+}
+
+/// Tells the player whether saving worked, fading out
+#[derive(Component)]
+struct SaveNotice(Timer);
+
+const SAVE_NOTICE_DURATION: Duration =
+    Duration::from_millis(2000);
+
+fn show_save_notice(
+    commands: &mut Commands,
+    message: impl Into<String>,
+) {
+    commands.spawn((
+        TextBundle::from_section(
+            message,
+            TextStyle {
+                font_size: 30.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            bottom: Val::Percent(10.),
+            ..default()
+        })
+        .with_text_justify(JustifyText::Center),
+        // above the pause menu it is usually saved from
+        ZIndex::Global(1),
+        SaveNotice(Timer::new(
+            SAVE_NOTICE_DURATION,
+            TimerMode::Once,
+        )),
+        InGame,
+    ));
+}
+
+fn fade_save_notice(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut notices: Query<(Entity, &mut SaveNotice, &mut Text)>,
+) {
+    for (entity, mut notice, mut text) in &mut notices {
+        notice.0.tick(time.delta());
+        if notice.0.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let alpha = notice.0.fraction_remaining();
+        for section in &mut text.sections {
+            section.style.color.set_a(alpha);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_the_epoch() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+    }
+
+    #[test]
+    fn formats_leap_days() {
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00");
+        assert_eq!(format_timestamp(1_709_164_800), "2024-02-29 00:00");
+    }
+
+    #[test]
+    fn formats_hours_and_minutes() {
+        assert_eq!(
+            format_timestamp(1_700_000_000),
+            "2023-11-14 22:13"
+        );
+        assert_eq!(
+            format_timestamp(4_102_444_799),
+            "2099-12-31 23:59"
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use rand::prelude::*;
+
+use crate::{
+    controller::camera_follows_player_system,
+    utils::pause_system::Pausable, CameraFollowsPlayer,
+};
+
+/// Jolts the camera around after big hits, fading out
+/// over a moment
+pub struct ScreenShakePlugin;
+
+impl Plugin for ScreenShakePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScreenShake>()
+            .add_systems(PreUpdate, settle_camera)
+            .add_systems(
+                Update,
+                shake_camera
+                    .after(camera_follows_player_system)
+                    .run_if(resource_equals(Pausable::NotPaused)),
+            );
+    }
+}
+
+/// `trauma` from 0 to 1, the shake grows with its square
+#[derive(Resource, Debug, Default)]
+pub struct ScreenShake {
+    trauma: f32,
+    offset: Vec2,
+}
+
+impl ScreenShake {
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.);
+    }
+}
+
+/// Furthest the camera moves at full trauma, in pixels
+const MAX_SHAKE_OFFSET: f32 = 24.;
+/// Trauma lost per second
+const TRAUMA_DECAY: f32 = 1.2;
+
+/// Takes last frame's jolt back out, so anything moving
+/// the camera this frame starts from where it really is
+fn settle_camera(
+    mut shake: ResMut<ScreenShake>,
+    mut cameras: Query<&mut Transform, With<CameraFollowsPlayer>>,
+) {
+    if shake.offset == Vec2::ZERO {
+        return;
+    }
+    for mut transform in &mut cameras {
+        transform.translation -= shake.offset.extend(0.);
+    }
+    shake.offset = Vec2::ZERO;
+}
+
+/// Jolts the camera by a fresh offset once it has been
+/// placed for the frame
+fn shake_camera(
+    time: Res<Time>,
+    mut shake: ResMut<ScreenShake>,
+    mut cameras: Query<&mut Transform, With<CameraFollowsPlayer>>,
+) {
+    if shake.trauma <= 0. {
+        return;
+    }
+    let angle = rand::thread_rng().gen_range(0.0..TAU);
+    shake.offset = Vec2::from_angle(angle)
+        * MAX_SHAKE_OFFSET
+        * shake.trauma.powi(2);
+    shake.trauma = (shake.trauma
+        - TRAUMA_DECAY * time.delta_seconds())
+    .max(0.);
+    for mut transform in &mut cameras {
+        transform.translation += shake.offset.extend(0.);
+    }
+}